@@ -1,9 +1,163 @@
-use riven::RiotApi;
+use std::fmt::Display;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use riven::reqwest::StatusCode;
+use riven::{RiotApi, RiotApiConfig, RiotApiError};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Base URL overriding the real Riot endpoints, `{}` is replaced by the platform or region
+static BASE_URL: OnceLock<String> = OnceLock::new();
+
+static FIXTURE_MODE: OnceLock<FixtureMode> = OnceLock::new();
+
+static API_KEY: OnceLock<String> = OnceLock::new();
+
+/// Recording or replaying of API responses. Responses are stored as `<dir>/<route>/<path>.json`,
+/// the same layout the mock server serves, so a recording can also be served by it.
+#[derive(Debug, Clone)]
+pub enum FixtureMode {
+    /// Write every response received to the directory
+    Record(PathBuf),
+    /// Answer every request from the directory instead of the API
+    Replay(PathBuf),
+}
+
+/// An error of a request sent through `request`
+#[derive(Debug)]
+pub enum Error {
+    Riot(RiotApiError),
+    /// No API key was set and the request isn't replayed
+    NoApiKey,
+    /// The response couldn't be recorded with `FixtureMode::Record`
+    Record(String),
+    /// There is no usable recorded response with `FixtureMode::Replay`
+    Replay(String),
+}
+
+impl Error {
+    /// The HTTP status code of the failed response, if the request was sent
+    pub fn status_code(&self) -> Option<StatusCode> {
+        match self {
+            Error::Riot(e) => e.status_code(),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Riot(e) => write!(f, "{e}"),
+            Error::NoApiKey => write!(
+                f,
+                "No API key, set RGAPI_KEY or api.key_file in the config file"
+            ),
+            Error::Record(e) | Error::Replay(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<RiotApiError> for Error {
+    fn from(e: RiotApiError) -> Self {
+        Error::Riot(e)
+    }
+}
 
 lazy_static::lazy_static! {
+    /// Without an API key, requests are refused by `request` before they are sent
     pub static ref RIOT_API: RiotApi = {
-        let api_key = std::env::var("RGAPI_KEY")
-            .expect("RGAPI_KEY environment variable not defined.");
-        RiotApi::new(api_key)
+        let mut config = RiotApiConfig::with_key(API_KEY.get().cloned().unwrap_or_default());
+        if let Some(base_url) = BASE_URL.get() {
+            config = config.set_base_url(base_url);
+        }
+        RiotApi::new(config)
     };
 }
+
+/// Sets the key sent with every request. Must be called before `RIOT_API` is first used.
+pub fn set_api_key(api_key: String) {
+    API_KEY
+        .set(api_key)
+        .expect("API key should only be set once");
+}
+
+/// Points the API client at another server, e.g. `http://127.0.0.1:8080/{}` for the mock server.
+/// Must be called before `RIOT_API` is first used.
+pub fn set_base_url(base_url: String) {
+    BASE_URL
+        .set(base_url)
+        .expect("base URL should only be set once");
+}
+
+/// Enables recording or replaying of API responses. Must be called before `RIOT_API` is first used.
+pub fn set_fixture_mode(mode: FixtureMode) {
+    FIXTURE_MODE
+        .set(mode)
+        .expect("fixture mode should only be set once");
+}
+
+pub fn fixture_mode() -> Option<&'static FixtureMode> {
+    FIXTURE_MODE.get()
+}
+
+/// Sends a request through `RIOT_API`, recording or replaying the response if enabled
+/// # Parameters
+/// `route` - the platform or region the request is sent to
+/// `path` - the endpoint path of the request, as sent by riven
+/// `request` - the request itself, only awaited if not replaying
+/// # Returns
+/// The response of the request. When replaying, a missing fixture is treated as "not found" for
+/// optional responses and is an error otherwise.
+pub async fn request<T, F>(route: &str, path: &str, request: F) -> Result<T, Error>
+where
+    T: Serialize + DeserializeOwned,
+    F: Future<Output = Result<T, RiotApiError>>,
+{
+    match FIXTURE_MODE.get() {
+        /* Nothing is sent when replaying, so no key is needed */
+        Some(FixtureMode::Replay(_)) => (),
+        _ if API_KEY.get().is_none() => return Err(Error::NoApiKey),
+        _ => (),
+    }
+
+    match FIXTURE_MODE.get() {
+        None => Ok(request.await?),
+        Some(FixtureMode::Record(dir)) => {
+            let response = request.await?;
+            write_fixture(&fixture_path(dir, route, path), &response)
+                .map_err(|e| Error::Record(format!("Error when recording {route}{path}: {e}")))?;
+            Ok(response)
+        }
+        Some(FixtureMode::Replay(dir)) => {
+            let file = fixture_path(dir, route, path);
+            let json = std::fs::read_to_string(&file).unwrap_or_else(|_| "null".to_string());
+            serde_json::from_str(&json).map_err(|e| {
+                Error::Replay(format!(
+                    "No usable recorded response for {route}{path} in {}: {e}",
+                    dir.display()
+                ))
+            })
+        }
+    }
+}
+
+fn fixture_path(dir: &Path, route: &str, path: &str) -> PathBuf {
+    let mut result = dir.join(route).join(path.trim_start_matches('/'));
+    result.as_mut_os_string().push(".json");
+    result
+}
+
+fn write_fixture<T: Serialize>(
+    file: &Path,
+    response: &T,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(file, serde_json::to_string_pretty(response)?)?;
+    Ok(())
+}
@@ -1,14 +1,93 @@
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use clap::{Parser, Subcommand};
+use riven::consts::{PlatformRoute, Tier};
+
+use crate::pro_data::leaderboard::SortBy;
+use crate::pro_data::{parse_platform, parse_tier, RiotId};
+use crate::ui::game::{Column, Layout};
+use crate::ui::{ColorWhen, OutputFormat};
+use crate::Storage;
 
 #[derive(Parser, Debug)]
 pub struct Args {
-    /// Path to the CSV file containing pros
-    #[arg(short, long, default_value = "/home/isak102/.local/share/pros.csv")]
-    pub pro_file_path: String,
+    /// Config file [default: $XDG_CONFIG_HOME/lol-pros/config.toml]
+    #[arg(long, value_name = "FILE", env = "LOL_PROS_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Path to the CSV file or SQLite database containing pros [default: pros.csv in the data
+    /// directory]
+    #[arg(short, long)]
+    pub pro_file_path: Option<String>,
 
-    // TODO: find way to disable color for table printing too
-    /// Disable colors [doesn't work with tables] (CLICOLOR=0 takes precedence over this option)
+    /// How pros and history are stored [default: sqlite if the pro file ends in .db, .sqlite or
+    /// .sqlite3, otherwise csv]
+    #[arg(long, value_enum)]
+    pub storage: Option<Storage>,
+
+    /// Region of pros that don't have one set in the CSV file, e.g. EUW, KR or NA [default: EUW1]
+    #[arg(short, long, value_parser = parse_platform)]
+    pub region: Option<PlatformRoute>,
+
+    /// Maximum number of pros to look up concurrently [default: 8]
     #[arg(short, long)]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Base URL of the Riot API, `{}` is replaced by the platform or region. Useful for pointing
+    /// the client at the mock server
+    #[arg(long, env = "RGAPI_BASE_URL")]
+    pub api_url: Option<String>,
+
+    /// Write every API response to this directory, in the layout served by the mock server
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Answer API requests from responses previously written by --record instead of the API
+    #[arg(long, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+
+    /// Directory for persistent data such as ladder history [default: $XDG_DATA_HOME/lol-pros]
+    #[arg(long, value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Directory for cached data [default: $XDG_CACHE_HOME/lol-pros]
+    #[arg(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// How many seconds a cached top league ladder is used before it is fetched again
+    /// [default: 600]
+    #[arg(long, value_name = "SECONDS")]
+    pub ladder_ttl: Option<u64>,
+
+    /// Ignore cached ladders and fetch them from the API
+    #[arg(long)]
+    pub refresh: bool,
+
+    /// Columns of each team in the live game table, from the edge of the table to the middle,
+    /// e.g. pro,rank,summoner,champion. Also available: spells, keystone, tier, winrate, games,
+    /// position, streak and region
+    #[arg(long, value_delimiter = ',', value_parser = Column::from_str)]
+    pub columns: Option<Vec<Column>>,
+
+    /// How live games are laid out [default: auto, which picks the widest layout that fits the
+    /// terminal]
+    #[arg(long, value_enum)]
+    pub layout: Option<Layout>,
+
+    /// How results are printed. Progress and errors always go to stderr
+    #[arg(short, long, value_enum, default_value = "table")]
+    pub output: OutputFormat,
+
+    /// When to color output [default: auto, which colors output to a terminal unless NO_COLOR is
+    /// set or CLICOLOR is 0, and always colors if CLICOLOR_FORCE is set]
+    #[arg(long, value_enum, value_name = "WHEN")]
+    pub color: Option<ColorWhen>,
+
+    /// Disable colors, same as --color never
+    #[arg(short, long, conflicts_with = "color")]
     pub disable_colors: bool,
 
     #[command(subcommand)]
@@ -17,11 +96,273 @@ pub struct Args {
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
-    /// Sync pro data
+    /// Sync pro data. Accounts that can't be resolved are reported at the end, and an
+    /// interrupted sync continues where it stopped when run again
     #[command(alias = "s")]
-    Sync {},
+    Sync {
+        /// Only print what would change, without writing the pro file
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Also check the IDs of accounts that have them, updating the Riot IDs of renamed
+        /// accounts and reporting accounts that no longer exist
+        #[arg(long)]
+        verify: bool,
+    },
+
+    /// Keep looking up live games on an interval, redrawing them in place as games start and
+    /// end. Stop with Ctrl-C
+    #[command(alias = "w")]
+    Watch {
+        /// Seconds between lookups [default: 60]
+        #[arg(short, long, value_name = "SECONDS")]
+        interval: Option<u64>,
+    },
+
+    /// Full-screen view of live games, the leaderboard and pro details, refreshed in the
+    /// background. Press ? for keys
+    #[command(alias = "t")]
+    Tui {
+        /// Seconds between lookups [default: 60]
+        #[arg(short, long, value_name = "SECONDS")]
+        interval: Option<u64>,
+    },
 
     /// Print pro players leaderboard
     #[command(alias = "l")]
-    Leaderboard {},
+    Leaderboard {
+        /// Show all ranked accounts of each pro instead of only their best one
+        #[arg(short, long)]
+        all_accounts: bool,
+
+        /// Show LP, games and rank changes since a duration ago (e.g. 24h, 7d) or a date
+        /// (e.g. 2023-05-01), using the stored ladder history
+        #[arg(short, long, value_parser = parse_since, required_if_eq("sort", "lp-gained"))]
+        since: Option<i64>,
+
+        /// Only show pros of this team, by short or full name
+        #[arg(short, long)]
+        team: Option<String>,
+
+        /// Only show pros whose best account is in this tier, e.g. challenger or GM
+        #[arg(long, value_parser = parse_tier)]
+        tier: Option<Tier>,
+
+        /// Only show pros whose best account is in this region, e.g. EUW or KR
+        #[arg(long, value_parser = parse_platform)]
+        region: Option<PlatformRoute>,
+
+        /// Only show pros with at least this many games played on their best account
+        #[arg(long, value_name = "GAMES")]
+        min_games: Option<u32>,
+
+        /// Only show pros with at least this winrate in percent on their best account
+        #[arg(long, value_name = "PERCENT")]
+        min_winrate: Option<f32>,
+
+        /// Only show the first N pros
+        #[arg(short = 'n', long, value_name = "N")]
+        top: Option<usize>,
+
+        /// What to sort by, highest first. Ties are broken by wins. lp-gained needs --since
+        #[arg(long, value_enum, default_value_t)]
+        sort: SortBy,
+    },
+
+    /// Print the teams of ranked pros, ordered by the average LP of their pros
+    Teams {},
+
+    /// Print the recorded results of a pro's games
+    #[command(alias = "m")]
+    Matches {
+        /// Name of the pro, optionally prefixed by their team, e.g. "Caps" or "G2 Caps"
+        pro: String,
+    },
+
+    /// Print the ladder history of a pro
+    #[command(alias = "h")]
+    History {
+        /// Name of the pro, optionally prefixed by their team, e.g. "Caps" or "G2 Caps"
+        pro: String,
+    },
+
+    /// Check the pro file for malformed rows, missing IDs, duplicate accounts and inconsistent
+    /// team names. Exits with status 1 if any problem is found
+    #[command(alias = "v")]
+    Validate {},
+
+    /// Add, remove or edit pros
+    Pro {
+        #[command(subcommand)]
+        command: ProCommand,
+    },
+
+    /// Import a pros CSV file, and the ladder and match history in the data directory, into the
+    /// SQLite database given by --pro-file-path
+    Migrate {
+        /// The CSV file to import
+        csv_file: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProCommand {
+    /// Add an account to a pro, adding the pro if they don't exist. The account is looked up right
+    /// away, so its Riot ID must be correct
+    Add {
+        /// Short name of the team, e.g. G2
+        team: String,
+
+        /// Name of the pro
+        name: String,
+
+        /// Riot ID of the account, e.g. "Caps#EUW"
+        riot_id: RiotId,
+
+        /// Region of the account [default: --region]
+        #[arg(long, value_parser = parse_platform)]
+        account_region: Option<PlatformRoute>,
+
+        /// Full name of the team, e.g. "G2 Esports". Only needed for new teams
+        #[arg(long)]
+        team_name: Option<String>,
+    },
+
+    /// Remove a pro, or one of their accounts
+    Remove {
+        /// Name of the pro, optionally prefixed by their team, e.g. "Caps" or "G2 Caps"
+        pro: String,
+
+        /// Only remove the account with this Riot ID
+        #[arg(long)]
+        account: Option<RiotId>,
+    },
+
+    /// Change the name or team of a pro
+    Edit {
+        /// Name of the pro, optionally prefixed by their team, e.g. "Caps" or "G2 Caps"
+        pro: String,
+
+        /// New name of the pro
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Short name of the pro's new team
+        #[arg(long)]
+        team: Option<String>,
+
+        /// Full name of the pro's team, changed for every pro of the team
+        #[arg(long)]
+        team_name: Option<String>,
+    },
+}
+
+/// Parses a duration ago such as `30m`, `24h`, `7d` or `2w`, or a local date or date and time such
+/// as `2023-05-01` or `2023-05-01T18:00`
+/// # Returns
+/// The point in time as a unix timestamp in seconds
+fn parse_since(s: &str) -> Result<i64, String> {
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M") {
+        return local_timestamp(date_time, s);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return local_timestamp(date.and_hms_opt(0, 0, 0).expect("midnight is valid"), s);
+    }
+
+    let unit_seconds = match s.chars().last() {
+        Some('m') => 60,
+        Some('h') => 60 * 60,
+        Some('d') => 24 * 60 * 60,
+        Some('w') => 7 * 24 * 60 * 60,
+        _ => return Err(format!("\"{s}\" is neither a duration like 24h nor a date")),
+    };
+    let amount: i64 = s[..s.len() - 1]
+        .parse()
+        .map_err(|_| format!("\"{s}\" is neither a duration like 24h nor a date"))?;
+    if amount <= 0 {
+        return Err(format!("\"{s}\" is not a positive duration"));
+    }
+
+    amount
+        .checked_mul(unit_seconds)
+        .and_then(|seconds| Utc::now().timestamp().checked_sub(seconds))
+        .ok_or_else(|| format!("\"{s}\" is too long ago"))
+}
+
+fn local_timestamp(date_time: NaiveDateTime, s: &str) -> Result<i64, String> {
+    match Local.from_local_datetime(&date_time).earliest() {
+        Some(t) => Ok(t.timestamp()),
+        None => Err(format!("\"{s}\" does not exist in the local timezone")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that `s` is parsed as `seconds` ago
+    fn assert_ago(s: &str, seconds: i64) {
+        let before = Utc::now().timestamp();
+        let since = parse_since(s).expect("duration should parse");
+        let after = Utc::now().timestamp();
+        assert!(
+            (before - seconds..=after - seconds).contains(&since),
+            "{s} parsed as {since}"
+        );
+    }
+
+    #[test]
+    fn parse_since_durations() {
+        assert_ago("30m", 30 * 60);
+        assert_ago("24h", 24 * 60 * 60);
+        assert_ago("7d", 7 * 24 * 60 * 60);
+        assert_ago("2w", 2 * 7 * 24 * 60 * 60);
+    }
+
+    #[test]
+    fn parse_since_dates() {
+        let local = |s| {
+            let date_time = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M").unwrap();
+            Local
+                .from_local_datetime(&date_time)
+                .earliest()
+                .unwrap()
+                .timestamp()
+        };
+        assert_eq!(parse_since("2023-05-01"), Ok(local("2023-05-01T00:00")));
+        assert_eq!(
+            parse_since("2023-05-01T18:00"),
+            Ok(local("2023-05-01T18:00"))
+        );
+    }
+
+    #[test]
+    fn parse_since_rejects_bad_durations() {
+        assert_eq!(
+            parse_since("0h"),
+            Err("\"0h\" is not a positive duration".to_string())
+        );
+        assert_eq!(
+            parse_since("-5d"),
+            Err("\"-5d\" is not a positive duration".to_string())
+        );
+        assert_eq!(
+            parse_since("99999999999999w"),
+            Err("\"99999999999999w\" is too long ago".to_string())
+        );
+        for s in [
+            "",
+            "h",
+            "5",
+            "5y",
+            "yesterday",
+            "2023-13-01",
+            "2023-05-01 18:00",
+        ] {
+            assert_eq!(
+                parse_since(s),
+                Err(format!("\"{s}\" is neither a duration like 24h nor a date"))
+            );
+        }
+    }
 }
@@ -0,0 +1,147 @@
+//! Mock Riot API server serving JSON fixtures from a directory, so the CLI can run without network
+//! access or a real API key.
+//!
+//! A request for `/<route>/<path>` is answered with `<fixture dir>/<route>/<path>.json`, where
+//! `<route>` is the platform (`EUW1`, `KR`, ...) or region (`EUROPE`, ...). Missing fixtures are
+//! answered with 404, which the API treats as "not found", e.g. a summoner not being in game.
+//!
+//! ```sh
+//! cargo run --bin mock-riot -- fixtures/mock 127.0.0.1:8080 &
+//! RGAPI_KEY=mock cargo run -- --api-url 'http://127.0.0.1:8080/{}' -p fixtures/mock/pros.csv
+//! ```
+//!
+//! `tests/mock_api.rs` runs the CLI against it the same way.
+
+use std::path::{Component, Path, PathBuf};
+use std::process;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let fixture_dir = match args.next() {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            eprintln!("Usage: mock-riot <fixture dir> [address]");
+            process::exit(1);
+        }
+    };
+    let address = args.next().unwrap_or(DEFAULT_ADDRESS.to_string());
+
+    let listener = TcpListener::bind(&address).await.unwrap_or_else(|e| {
+        eprintln!("Could not bind to {address}: {e}");
+        process::exit(1);
+    });
+    eprintln!(
+        "Serving fixtures from {} on http://{address}/{{}}",
+        fixture_dir.display()
+    );
+
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Error accepting connection: {e}");
+                continue;
+            }
+        };
+        let fixture_dir = fixture_dir.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &fixture_dir).await {
+                eprintln!("Error handling request: {e}");
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, fixture_dir: &Path) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    /* Only the request line is needed, so read until the end of the headers */
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..n]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+    let (method, target) = (request_line.next(), request_line.next());
+
+    let (status, body) = match (method, target) {
+        (Some("GET"), Some(target)) => match fixture_path(fixture_dir, target) {
+            Some(path) => match tokio::fs::read(&path).await {
+                Ok(body) => ("200 OK", body),
+                Err(_) => ("404 Not Found", not_found_body()),
+            },
+            None => ("400 Bad Request", Vec::new()),
+        },
+        _ => ("405 Method Not Allowed", Vec::new()),
+    };
+    eprintln!(
+        "{} {} -> {status}",
+        method.unwrap_or("?"),
+        target.unwrap_or("?")
+    );
+
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await
+}
+
+/// Maps a request target to the fixture file answering it
+/// # Returns
+/// `None` if the target tries to escape the fixture directory
+fn fixture_path(fixture_dir: &Path, target: &str) -> Option<PathBuf> {
+    let path = target.split('?').next().unwrap_or(target);
+    let relative = PathBuf::from(percent_decode(path.trim_start_matches('/')));
+
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let mut result = fixture_dir.join(relative);
+    result.as_mut_os_string().push(".json");
+    Some(result)
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                result.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                result.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).to_string()
+}
+
+fn not_found_body() -> Vec<u8> {
+    br#"{"status":{"message":"Data not found","status_code":404}}"#.to_vec()
+}
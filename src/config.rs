@@ -0,0 +1,509 @@
+//! Settings, read from `$XDG_CONFIG_HOME/lol-pros/config.toml` (or `--config`) and overridden by
+//! command line arguments. Every key is optional:
+//!
+//! ```toml
+//! pro_file = "~/.local/share/lol-pros/pros.csv"
+//! storage = "csv"            # or "sqlite", guessed from the pro file extension by default
+//! data_dir = "~/.local/share/lol-pros"
+//! cache_dir = "~/.cache/lol-pros"
+//! region = "EUW1"            # region of accounts that don't have one
+//!
+//! [api]
+//! key_env = "RGAPI_KEY"      # environment variable holding the API key
+//! key_file = "~/.config/lol-pros/api-key"
+//! base_url = "http://127.0.0.1:8080/{}"
+//!
+//! [display]
+//! columns = ["pro", "rank", "summoner", "champion"]  # also spells, keystone, tier, winrate,
+//!                                                   # games, position, streak and region
+//! layout = "auto"            # or "side-by-side", "stacked" or "compact"
+//! color = "auto"             # or "always" or "never"
+//!
+//! # Colors are black, red, green, yellow, blue, magenta, cyan and white, also prefixed with
+//! # bright-, e.g. bright-blue
+//! [theme]
+//! blue = "blue"
+//! red = "red"
+//! pro = "yellow"             # pro names in games, their team's color by default
+//! challenger = "bright-yellow"
+//! grandmaster = "red"
+//! master = "magenta"
+//!
+//! [polling]
+//! jobs = 8                   # pros looked up concurrently
+//! ladder_ttl = 600           # seconds a cached ladder is used
+//! interval = 60              # seconds between lookups in watch mode and the TUI
+//! ```
+
+use std::error::Error;
+use std::io::{stdout, IsTerminal};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use riven::consts::PlatformRoute;
+
+use crate::args::{Args, Command};
+use crate::pro_data::{db, parse_platform};
+use crate::ui::game::{Column, Layout};
+use crate::ui::theme::{Theme, ThemeColor};
+use crate::ui::{ColorWhen, OutputFormat};
+
+const DEFAULT_KEY_ENV: &str = "RGAPI_KEY";
+
+/// Where pros, ladder history and match results are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Storage {
+    /// A CSV file of pros, with history in CSV and JSON files in the data directory
+    Csv,
+    /// A single SQLite database holding pros and history
+    Sqlite,
+}
+
+#[derive(Clone)]
+pub struct Config {
+    pub pro_file_path: String, // FIXME: turn this into a path
+    pub storage: Storage,
+    /// Region used for pros that don't specify one
+    pub region: PlatformRoute,
+    pub data_dir: PathBuf,
+    pub cache_dir: PathBuf,
+    /// How long a cached top league ladder is used
+    pub ladder_ttl: Duration,
+    /// How often games are looked up in watch mode
+    pub watch_interval: Duration,
+    /// Whether cached data should be ignored
+    pub refresh: bool,
+    /// Maximum number of pros to look up concurrently
+    pub jobs: NonZeroUsize,
+    /// `None` if no key is configured
+    pub api_key: Option<String>,
+    /// Base URL of the Riot API, `{}` is replaced by the platform or region
+    pub api_url: Option<String>,
+    /// Columns of each team in the live game table, from the edge of the table to the middle
+    pub columns: Vec<Column>,
+    pub layout: Layout,
+    /// Whether output is colored, decided from `--color` or `display.color` and the environment
+    pub colors: bool,
+    pub theme: Theme,
+    pub output: OutputFormat,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    pro_file: Option<PathBuf>,
+    storage: Option<Storage>,
+    data_dir: Option<PathBuf>,
+    cache_dir: Option<PathBuf>,
+    region: Option<String>,
+    api: ApiSection,
+    display: DisplaySection,
+    polling: PollingSection,
+    theme: ThemeSection,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ApiSection {
+    /// The API key itself, better kept in `key_file` or `key_env`
+    key: Option<String>,
+    key_env: Option<String>,
+    key_file: Option<PathBuf>,
+    base_url: Option<String>,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DisplaySection {
+    columns: Option<Vec<String>>,
+    layout: Option<Layout>,
+    color: Option<ColorWhen>,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PollingSection {
+    jobs: Option<NonZeroUsize>,
+    ladder_ttl: Option<u64>,
+    interval: Option<u64>,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeSection {
+    blue: Option<ThemeColor>,
+    red: Option<ThemeColor>,
+    pro: Option<ThemeColor>,
+    challenger: Option<ThemeColor>,
+    grandmaster: Option<ThemeColor>,
+    master: Option<ThemeColor>,
+}
+
+impl ThemeSection {
+    fn theme(self) -> Theme {
+        let default = Theme::default();
+        Theme {
+            blue: self.blue.unwrap_or(default.blue),
+            red: self.red.unwrap_or(default.red),
+            pro: self.pro.or(default.pro),
+            challenger: self.challenger.unwrap_or(default.challenger),
+            grandmaster: self.grandmaster.unwrap_or(default.grandmaster),
+            master: self.master.unwrap_or(default.master),
+        }
+    }
+}
+
+impl Config {
+    /// Builds the config from the config file and the command line arguments, which take
+    /// precedence. A missing config file is only an error if it was given explicitly.
+    pub fn load(args: &Args) -> Result<Config, Box<dyn Error>> {
+        let file = match &args.config {
+            Some(path) => read_config_file(path)?,
+            None => match default_config_path() {
+                Some(path) if path.exists() => read_config_file(&path)?,
+                _ => ConfigFile::default(),
+            },
+        };
+
+        let data_dir = args
+            .data_dir
+            .clone()
+            .or(file.data_dir.map(|d| expand_home(&d)))
+            .unwrap_or_else(|| {
+                dirs::data_dir()
+                    .unwrap_or_else(std::env::temp_dir)
+                    .join("lol-pros")
+            });
+        let cache_dir = args
+            .cache_dir
+            .clone()
+            .or(file.cache_dir.map(|d| expand_home(&d)))
+            .unwrap_or_else(|| {
+                dirs::cache_dir()
+                    .unwrap_or_else(std::env::temp_dir)
+                    .join("lol-pros")
+            });
+
+        let pro_file_path = match (&args.pro_file_path, file.pro_file) {
+            (Some(path), _) => path.clone(),
+            (None, Some(path)) => expand_home(&path).to_string_lossy().to_string(),
+            (None, None) => data_dir.join("pros.csv").to_string_lossy().to_string(),
+        };
+        let storage = args.storage.or(file.storage).unwrap_or_else(|| {
+            match db::is_database_path(pro_file_path.as_ref()) {
+                true => Storage::Sqlite,
+                false => Storage::Csv,
+            }
+        });
+
+        let region = match (args.region, file.region) {
+            (Some(region), _) => region,
+            (None, Some(region)) => parse_platform(&region)?,
+            (None, None) => PlatformRoute::EUW1,
+        };
+
+        let (columns, source) = match (&args.columns, file.display.columns) {
+            (Some(columns), _) => (columns.clone(), "--columns"),
+            (None, Some(columns)) => (
+                columns
+                    .iter()
+                    .map(|c| c.parse())
+                    .collect::<Result<Vec<Column>, String>>()?,
+                "display.columns in the config file",
+            ),
+            (None, None) => (Column::defaults(), "default columns"),
+        };
+        validate_columns(&columns, source)?;
+
+        let watch_interval = match args.command {
+            Some(
+                Command::Watch {
+                    interval: Some(interval),
+                }
+                | Command::Tui {
+                    interval: Some(interval),
+                },
+            ) => interval,
+            _ => file.polling.interval.unwrap_or(60),
+        };
+        if watch_interval == 0 {
+            return Err("The watch interval must be at least 1 second".into());
+        }
+
+        let color = match args.disable_colors {
+            true => ColorWhen::Never,
+            false => args.color.or(file.display.color).unwrap_or(ColorWhen::Auto),
+        };
+
+        Ok(Config {
+            pro_file_path,
+            storage,
+            region,
+            data_dir,
+            cache_dir,
+            ladder_ttl: Duration::from_secs(
+                args.ladder_ttl.or(file.polling.ladder_ttl).unwrap_or(600),
+            ),
+            watch_interval: Duration::from_secs(watch_interval),
+            refresh: args.refresh,
+            jobs: args
+                .jobs
+                .or(file.polling.jobs)
+                .unwrap_or(NonZeroUsize::new(8).expect("8 is not zero")),
+            api_key: api_key(&file.api, |name| std::env::var(name).ok())?,
+            api_url: args.api_url.clone().or(file.api.base_url),
+            columns,
+            layout: args.layout.or(file.display.layout).unwrap_or(Layout::Auto),
+            colors: color.enabled(|name| std::env::var(name).ok(), stdout().is_terminal()),
+            theme: file.theme.theme(),
+            output: args.output,
+        })
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("lol-pros").join("config.toml"))
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read config file {}: {e}", path.display()))?;
+    toml::from_str(&contents)
+        .map_err(|e| format!("Invalid config file {}: {e}", path.display()).into())
+}
+
+/// Checks that there is at least one column and that none is given twice
+/// # Parameters
+/// `source` - where the columns come from, for the error message
+fn validate_columns(columns: &[Column], source: &str) -> Result<(), String> {
+    if columns.is_empty() {
+        return Err(format!("{source} must not be empty"));
+    }
+    if let Some((_, duplicate)) = columns
+        .iter()
+        .enumerate()
+        .find(|(i, column)| columns[..*i].contains(column))
+    {
+        return Err(format!(
+            "{source} has the column \"{}\" more than once",
+            duplicate.name()
+        ));
+    }
+    Ok(())
+}
+
+/// Gets the API key from the config file, a key file or an environment variable, in that order
+/// # Parameters
+/// `var` - looks up an environment variable
+fn api_key(
+    api: &ApiSection,
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<Option<String>, Box<dyn Error>> {
+    if let Some(key) = &api.key {
+        return Ok(Some(key.clone()));
+    }
+    if let Some(path) = &api.key_file {
+        let path = expand_home(path);
+        let key = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read API key file {}: {e}", path.display()))?;
+        return Ok(Some(key.trim().to_string()));
+    }
+
+    let key_env = api.key_env.as_deref().unwrap_or(DEFAULT_KEY_ENV);
+    Ok(var(key_env))
+}
+
+/// Replaces a leading `~` with the home directory
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn load(dir: &Path, config_file: &str, args: &[&str]) -> Result<Config, Box<dyn Error>> {
+        std::fs::write(dir.join("config.toml"), config_file).expect("should write config file");
+        Config::load(&test_util::args(dir, args))
+    }
+
+    #[test]
+    fn load_reads_config_file() {
+        let dir = test_util::temp_dir("load_reads_config_file");
+        let config = load(
+            &dir,
+            "region = \"KR\"\n\
+             [display]\n\
+             layout = \"stacked\"\n\
+             [polling]\n\
+             jobs = 2\n\
+             ladder_ttl = 100\n\
+             interval = 30\n",
+            &["watch"],
+        )
+        .expect("config should load");
+
+        assert_eq!(config.region, PlatformRoute::KR);
+        assert_eq!(config.layout, Layout::Stacked);
+        assert_eq!(config.jobs.get(), 2);
+        assert_eq!(config.ladder_ttl, Duration::from_secs(100));
+        assert_eq!(config.watch_interval, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn load_prefers_command_line() {
+        let dir = test_util::temp_dir("load_prefers_command_line");
+        let config = load(
+            &dir,
+            "region = \"KR\"\n\
+             pro_file = \"/nonexistent/pros.csv\"\n\
+             [display]\n\
+             layout = \"stacked\"\n\
+             [polling]\n\
+             jobs = 2\n\
+             ladder_ttl = 100\n\
+             interval = 30\n",
+            &[
+                "--region",
+                "EUW",
+                "--layout",
+                "compact",
+                "--jobs",
+                "3",
+                "--ladder-ttl",
+                "5",
+                "watch",
+                "--interval",
+                "10",
+            ],
+        )
+        .expect("config should load");
+
+        assert_eq!(config.pro_file_path, dir.join("pros.csv").to_string_lossy());
+        assert_eq!(config.region, PlatformRoute::EUW1);
+        assert_eq!(config.layout, Layout::Compact);
+        assert_eq!(config.jobs.get(), 3);
+        assert_eq!(config.ladder_ttl, Duration::from_secs(5));
+        assert_eq!(config.watch_interval, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn load_rejects_zero_interval() {
+        let dir = test_util::temp_dir("load_rejects_zero_interval");
+        for (config_file, args) in [
+            ("[polling]\ninterval = 0\n", &["watch"][..]),
+            ("", &["tui", "--interval", "0"][..]),
+        ] {
+            let e = load(&dir, config_file, args)
+                .err()
+                .expect("zero interval should be rejected");
+            assert_eq!(
+                e.to_string(),
+                "The watch interval must be at least 1 second"
+            );
+        }
+    }
+
+    #[test]
+    fn load_rejects_zero_jobs() {
+        let dir = test_util::temp_dir("load_rejects_zero_jobs");
+        let e = load(&dir, "[polling]\njobs = 0\n", &[])
+            .err()
+            .expect("zero jobs should be rejected");
+        assert!(e.to_string().starts_with("Invalid config file "), "{e}");
+    }
+
+    #[test]
+    fn validate_columns_rejects_empty_and_duplicate_columns() {
+        assert_eq!(validate_columns(&Column::defaults(), "--columns"), Ok(()));
+        assert_eq!(
+            validate_columns(&[], "--columns"),
+            Err("--columns must not be empty".to_string())
+        );
+        assert_eq!(
+            validate_columns(
+                &[Column::ProName, Column::RankInfo, Column::ProName],
+                "--columns"
+            ),
+            Err("--columns has the column \"pro\" more than once".to_string())
+        );
+    }
+
+    #[test]
+    fn load_rejects_bad_columns() {
+        let dir = test_util::temp_dir("load_rejects_bad_columns");
+        for (config_file, args, message) in [
+            (
+                "[display]\ncolumns = []\n",
+                &[][..],
+                "display.columns in the config file must not be empty",
+            ),
+            (
+                "[display]\ncolumns = [\"rank\", \"champion\", \"rank\"]\n",
+                &[][..],
+                "display.columns in the config file has the column \"rank\" more than once",
+            ),
+            (
+                "[display]\ncolumns = [\"rank\", \"rank\"]\n",
+                &["--columns", "pro,champion,champion"][..],
+                "--columns has the column \"champion\" more than once",
+            ),
+        ] {
+            let e = load(&dir, config_file, args)
+                .err()
+                .expect("columns should be rejected");
+            assert_eq!(e.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn api_key_prefers_key_then_key_file_then_environment() {
+        let dir = test_util::temp_dir("api_key_prefers_key_then_key_file_then_environment");
+        let key_file = dir.join("api-key");
+        std::fs::write(&key_file, "RGAPI-file\n").expect("should write key file");
+        let var = |name: &str| match name {
+            DEFAULT_KEY_ENV => Some("RGAPI-default-env".to_string()),
+            "MY_KEY" => Some("RGAPI-env".to_string()),
+            _ => None,
+        };
+
+        let mut api = ApiSection {
+            key: Some("RGAPI-key".to_string()),
+            key_file: Some(key_file),
+            key_env: Some("MY_KEY".to_string()),
+            ..ApiSection::default()
+        };
+        assert_eq!(api_key(&api, var).unwrap().as_deref(), Some("RGAPI-key"));
+        api.key = None;
+        assert_eq!(api_key(&api, var).unwrap().as_deref(), Some("RGAPI-file"));
+        api.key_file = None;
+        assert_eq!(api_key(&api, var).unwrap().as_deref(), Some("RGAPI-env"));
+        api.key_env = None;
+        assert_eq!(
+            api_key(&api, var).unwrap().as_deref(),
+            Some("RGAPI-default-env")
+        );
+        assert_eq!(api_key(&api, |_| None).unwrap(), None);
+    }
+
+    #[test]
+    fn api_key_reports_missing_key_file() {
+        let dir = test_util::temp_dir("api_key_reports_missing_key_file");
+        let api = ApiSection {
+            key_file: Some(dir.join("api-key")),
+            ..ApiSection::default()
+        };
+        let e = api_key(&api, |_| Some("RGAPI-env".to_string()))
+            .expect_err("missing key file should be an error");
+        assert!(
+            e.to_string().starts_with("Could not read API key file "),
+            "{e}"
+        );
+    }
+}
@@ -1,58 +1,180 @@
 mod api;
 mod args;
+mod config;
 mod pro_data;
+#[cfg(test)]
+mod test_util;
+mod tui;
 mod ui;
+mod watch;
 
+use std::collections::HashSet;
 use std::process;
 
 use clap::Parser;
+use futures::stream::{self, StreamExt};
 use pro_data::*;
 use riven::reqwest::StatusCode;
-use yansi::Paint;
 
-pub struct Config {
-    pub pro_file_path: String, // FIXME: turn this into a path
-}
+pub use config::{Config, Storage};
+use ui::OutputFormat;
 
 #[tokio::main]
 async fn main() {
     let args = args::Args::parse();
-    let disable_colors;
+    let c = Config::load(&args).unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        process::exit(1);
+    });
 
-    // disable color if CLICOLOR is set to 0
-    if let Ok(true) = std::env::var("CLICOLOR").map(|v| v == "0") {
-        disable_colors = true;
-    } else {
-        disable_colors = args.disable_colors;
+    ui::set_colors(c.colors);
+    ui::set_theme(c.theme.clone());
+
+    if let Some(api_key) = &c.api_key {
+        api::set_api_key(api_key.clone());
+    }
+    if let Some(api_url) = &c.api_url {
+        api::set_base_url(api_url.clone());
     }
-    if disable_colors {
-        Paint::disable();
+    if let Some(dir) = &args.record {
+        api::set_fixture_mode(api::FixtureMode::Record(dir.clone()));
+    } else if let Some(dir) = &args.replay {
+        api::set_fixture_mode(api::FixtureMode::Replay(dir.clone()));
     }
 
-    let c = Config {
-        pro_file_path: args.pro_file_path,
-    };
+    if let Some(args::Command::Migrate { csv_file }) = &args.command {
+        if c.storage != Storage::Sqlite {
+            eprintln!("Migrating needs a SQLite database as pro file, e.g. -p pros.db");
+            process::exit(1);
+        }
+        let csv_config = Config {
+            pro_file_path: csv_file.clone(),
+            storage: Storage::Csv,
+            ..c.clone()
+        };
+        match pro_data::db::migrate_csv(&c, &csv_config) {
+            Ok((accounts, snapshots, results)) => {
+                eprintln!(
+                    "Imported {accounts} account(s), {snapshots} ladder snapshot(s) and \
+                     {results} match result(s) into {}",
+                    c.pro_file_path
+                );
+                if c.output != OutputFormat::Table {
+                    ui::json::print_migration(&c.pro_file_path, accounts, snapshots, results);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error when migrating to {}: {e}", c.pro_file_path);
+                process::exit(1);
+            }
+        }
+        process::exit(0);
+    }
 
-    match &args.command {
-        Some(cmd) => match cmd {
-            args::Command::Sync {} => {
-                pro_data::io::sync_summoner_ids(&c)
-                    .await
-                    .unwrap_or_else(|err| {
-                        eprintln!("Error when syncing summoner IDs: {}", err);
-                        process::exit(1);
-                    });
-                eprintln!("Done syncing summoner IDs");
-                process::exit(0);
+    if let Some(args::Command::Validate {}) = &args.command {
+        let problems = pro_data::validate::validate(&c).unwrap_or_else(|e| {
+            eprintln!("Error when reading {}: {e}", c.pro_file_path);
+            process::exit(1);
+        });
+        match c.output {
+            OutputFormat::Table => {
+                for problem in &problems {
+                    println!("{}: {}", problem.location, problem.message);
+                }
             }
-            _ => {}
-        },
-        None => {}
+            format => ui::json::print_problems(format, &problems),
+        }
+        if !problems.is_empty() {
+            eprintln!("Found {} problem(s)", problems.len());
+            process::exit(1);
+        }
+        eprintln!("No problems found in {}", c.pro_file_path);
+        process::exit(0);
+    }
+
+    if let Some(args::Command::Pro { command }) = &args.command {
+        edit_pros(&c, command).await.unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            process::exit(1);
+        });
+        process::exit(0);
+    }
+
+    if let Some(args::Command::Sync { dry_run, verify }) = &args.command {
+        let report = pro_data::sync::sync_summoner_ids(&c, *dry_run, *verify)
+            .await
+            .unwrap_or_else(|err| {
+                eprintln!("Error when syncing summoner IDs: {}", err);
+                process::exit(1);
+            });
+        match c.output {
+            OutputFormat::Table => {
+                for change in &report.changes {
+                    println!("{change}");
+                }
+            }
+            _ => ui::json::print_sync_report(&report),
+        }
+        match dry_run {
+            true => eprintln!("Would sync {} account(s)", report.changes.len()),
+            false => eprintln!("Synced {} account(s)", report.changes.len()),
+        }
+        if !report.missing.is_empty() {
+            eprintln!("{} account(s) no longer exist:", report.missing.len());
+            for account in &report.missing {
+                eprintln!("  {account}");
+            }
+        }
+        if !report.failures.is_empty() {
+            eprintln!("Could not sync {} account(s):", report.failures.len());
+            for failure in &report.failures {
+                eprintln!("  {}: {}", failure.riot_id, failure.error);
+            }
+        }
+        if !report.missing.is_empty() || !report.failures.is_empty() {
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    if let Some(args::Command::History { pro } | args::Command::Matches { pro }) = &args.command {
+        let pros = pro_data::io::load_pros(&c).await.unwrap_or_else(|e| {
+            eprintln!("Error when loading pros: {e}");
+            process::exit(1);
+        });
+        let pro = match pros.iter().find(|p| p.matches(pro)) {
+            Some(p) => p,
+            None => {
+                eprintln!("No pro called {pro} in the database");
+                process::exit(1);
+            }
+        };
+
+        if let Some(args::Command::Matches { .. }) = &args.command {
+            let results = pro_data::tracker::load_results(&c, pro).unwrap_or_else(|e| {
+                eprintln!("Error when loading match history: {e}");
+                process::exit(1);
+            });
+            match c.output {
+                OutputFormat::Table => ui::matches::print(pro, &results),
+                format => ui::json::print_matches(format, pro, &results),
+            }
+        } else {
+            let snapshots = pro_data::history::load_snapshots(&c, pro).unwrap_or_else(|e| {
+                eprintln!("Error when loading ladder history: {e}");
+                process::exit(1);
+            });
+            match c.output {
+                OutputFormat::Table => ui::history::print(pro, &snapshots),
+                format => ui::json::print_history(format, pro, &snapshots),
+            }
+        }
+        process::exit(0);
     }
 
     eprintln!("Getting pros...");
-    let mut pro_data = ProData::load(&c).await.unwrap_or_else(|e| {
-        if let Some(e) = e.downcast_ref::<riven::RiotApiError>() {
+    let pro_data = ProData::load(&c).await.unwrap_or_else(|e| {
+        if let Some(e) = e.downcast_ref::<api::Error>() {
             if e.status_code() == Some(StatusCode::FORBIDDEN) {
                 eprintln!("ERROR: 403 received, probably due to bad API key");
             } else {
@@ -64,24 +186,87 @@ async fn main() {
         process::exit(1);
     });
 
-    match &args.command {
-        Some(cmd) => match cmd {
-            args::Command::Leaderboard {} => {
-                ui::leaderboard::print(&pro_data);
-                process::exit(0);
+    if let Some(args::Command::Leaderboard {
+        all_accounts,
+        since,
+        team,
+        tier,
+        region,
+        min_games,
+        min_winrate,
+        top,
+        sort,
+    }) = &args.command
+    {
+        let leaderboard = pro_data.pro_leaderboard();
+        let changes = since.map(|since| {
+            let snapshots = pro_data::history::load_all_snapshots(&c).unwrap_or_else(|e| {
+                eprintln!("Error when loading ladder history: {e}");
+                process::exit(1);
+            });
+            pro_data::history::changes_since(&leaderboard, &snapshots, since)
+        });
+        let filter = pro_data::leaderboard::Filter {
+            team: team.clone(),
+            tier: *tier,
+            platform: *region,
+            min_games: *min_games,
+            min_winrate: *min_winrate,
+        };
+        let show_changes = changes.is_some();
+        let entries = pro_data::leaderboard::entries(leaderboard, changes, &filter, *sort, *top);
+        match c.output {
+            OutputFormat::Table => {
+                ui::leaderboard::print(&pro_data, *all_accounts, &entries, show_changes)
             }
-            _ => {}
-        },
-        None => {}
+            format => ui::json::print_leaderboard(format, &pro_data, *all_accounts, &entries),
+        }
+        process::exit(0);
     }
 
-    let pros = &pro_data.get_pros();
-    for pro in pros {
-        if pro_data.is_in_game(pro) {
-            continue;
+    if let Some(args::Command::Teams {}) = &args.command {
+        let teams = pro_data::leaderboard::teams(&pro_data, pro_data.pro_leaderboard());
+        match c.output {
+            OutputFormat::Table => ui::teams::print(&teams),
+            format => ui::json::print_teams(format, &teams),
         }
-        let game = match pro_data.fetch_game(pro).await {
-            Err(e) => {
+        process::exit(0);
+    }
+
+    if let Some(args::Command::Tui { .. }) = &args.command {
+        if let Err(e) = tui::run(&c, pro_data).await {
+            eprintln!("Error: {e}");
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    if let Some(args::Command::Watch { .. }) = &args.command {
+        watch::watch(&c, pro_data).await;
+        process::exit(0);
+    }
+
+    let pro_data = &pro_data;
+    let mut printed_games = HashSet::new();
+    let mut json_output = match c.output {
+        OutputFormat::Table => None,
+        format => Some(ui::json::LiveOutput::new(format)),
+    };
+    let mut lookups = stream::iter(pro_data.get_pros())
+        .map(|pro| async move {
+            /* Skip pros that were found in a game by an earlier lookup */
+            if pro_data.is_in_game(&pro) {
+                return (pro, None);
+            }
+            let result = pro_data.fetch_game(&pro).await;
+            (pro, Some(result))
+        })
+        .buffer_unordered(c.jobs.get());
+
+    while let Some((pro, result)) = lookups.next().await {
+        let game = match result {
+            None => continue,
+            Some(Err(e)) => {
                 if e.status_code() == Some(StatusCode::FORBIDDEN) {
                     eprintln!("ERROR: 403 received, probably due to bad API key");
                     std::process::exit(1);
@@ -89,19 +274,50 @@ async fn main() {
                 eprintln!("Error when fetching game for {pro}: {}", e);
                 continue;
             }
-            Ok(result) => match result {
-                None => {
+            Some(Ok(None)) => {
+                if json_output.is_none() {
                     println!("<{pro}> offline...");
-                    continue;
                 }
-                Some(g) => g,
-            },
+                continue;
+            }
+            Some(Ok(Some(g))) => g,
         };
-        ui::game::print(&game)
-            .await
-            .expect("printing should succeed");
+        if !printed_games.insert(game.game_id()) {
+            continue;
+        }
+        match &mut json_output {
+            Some(output) => output.game(&game),
+            None => ui::game::print(&game, &c.columns, c.layout)
+                .await
+                .expect("printing should succeed"),
+        }
+    }
+
+    if let Err(e) = pro_data::tracker::track_games(&c, pro_data) {
+        eprintln!("Error when tracking games: {e}");
+    }
+    match pro_data::tracker::resolve_finished(&c, pro_data).await {
+        Ok(results) => {
+            for result in results {
+                eprintln!(
+                    "Recorded result of game {}: {} {}",
+                    result.game_id,
+                    result.riot_id,
+                    ui::matches::result_string(&result)
+                );
+            }
+        }
+        Err(e) => eprintln!("Error when recording game results: {e}"),
     }
 
+    if let Some(output) = json_output {
+        output.finish(ui::json::SummaryJson {
+            games: pro_data.games_count(),
+            pros_in_game: pro_data.pros_in_game_count(),
+            pros: pro_data.pros_count(),
+        });
+        return;
+    }
     println!(
         "\nFound {} game(s) with {} pro(s) in total. {} pro(s) exist in the database.",
         pro_data.games_count(),
@@ -109,3 +325,78 @@ async fn main() {
         pro_data.pros_count(),
     );
 }
+
+async fn edit_pros(
+    c: &Config,
+    command: &args::ProCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use pro_data::edit;
+
+    match command {
+        args::ProCommand::Add {
+            team,
+            name,
+            riot_id,
+            account_region,
+            team_name,
+        } => {
+            edit::add_account(
+                c,
+                team,
+                name,
+                riot_id,
+                *account_region,
+                team_name.as_deref(),
+            )
+            .await?;
+            eprintln!("Added {riot_id} to {} {name}", team.to_uppercase());
+            if c.output != OutputFormat::Table {
+                let query = format!("{} {name}", team.to_uppercase());
+                let pro = edit::find_pro(&pro_data::io::load_pros(c).await?, &query)?;
+                ui::json::print_pro_change("add", &pro, None, &[riot_id.to_string()]);
+            }
+        }
+        args::ProCommand::Remove { pro, account } => {
+            let pro = edit::find_pro(&pro_data::io::load_pros(c).await?, pro)?;
+            let removed = edit::remove(c, &pro, account.as_ref())?;
+            eprintln!("Removed {removed} account(s) of {pro}");
+            if c.output != OutputFormat::Table {
+                let riot_ids: Vec<String> = match account {
+                    Some(riot_id) => vec![riot_id.to_string()],
+                    None => pro
+                        .accounts()
+                        .iter()
+                        .map(|a| a.riot_id().to_string())
+                        .collect(),
+                };
+                ui::json::print_pro_change("remove", &pro, None, &riot_ids);
+            }
+        }
+        args::ProCommand::Edit {
+            pro,
+            name,
+            team,
+            team_name,
+        } => {
+            let pro = edit::find_pro(&pro_data::io::load_pros(c).await?, pro)?;
+            let changes = edit::ProEdit {
+                name: name.clone(),
+                team: team.clone(),
+                team_name: team_name.clone(),
+            };
+            edit::edit(c, &pro, &changes)?;
+            eprintln!("Edited {pro}");
+            if c.output != OutputFormat::Table {
+                let query = format!(
+                    "{} {}",
+                    team.as_deref().unwrap_or(pro.team()).to_uppercase(),
+                    name.as_deref().unwrap_or(pro.name())
+                );
+                let edited = edit::find_pro(&pro_data::io::load_pros(c).await?, &query)?;
+                ui::json::print_pro_change("edit", &edited, Some(&pro), &[]);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -3,36 +3,61 @@ use riven::models::league_v4::LeagueItem;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{Display, Write};
+use std::str::FromStr;
 
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 use riven::models::spectator_v4::*;
-use riven::RiotApiError;
 
 pub use self::pro_game::*;
 pub use self::top_leagues::*;
 use super::Config;
-use crate::api::RIOT_API;
+use crate::api::{self, RIOT_API};
+use crate::ui;
 
+pub mod db;
+pub mod edit;
+pub mod history;
 pub mod io;
+pub mod leaderboard;
 mod pro_game;
+pub mod sync;
+#[cfg(test)]
+mod test_util;
 mod top_leagues;
+pub mod tracker;
+pub mod validate;
 
 pub type SummonerID = String;
-pub type SummonerName = String;
+pub type Puuid = String;
+
+/// A Riot ID of the form `gameName#tagLine`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiotId {
+    pub game_name: String,
+    pub tag_line: String,
+}
 
 #[derive(Debug, Clone)]
 pub struct Pro {
     player_name: String,
     team: Team,
-    summoner_name: String,
-    summoner_id: Option<String>,
+    accounts: Vec<Account>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Account {
+    riot_id: RiotId,
+    platform: PlatformRoute,
+    puuid: Option<Puuid>,
+    /// Encrypted summoner ID, still needed by the spectator-v4 and league-v4 endpoints
+    summoner_id: Option<SummonerID>,
 }
 
 #[derive(Debug, Clone)]
 struct Team {
     short_name: String,
-    _full_name: String,
+    full_name: String,
 }
 
 pub type RankedData = LeagueItem;
@@ -40,43 +65,195 @@ pub type RankedData = LeagueItem;
 pub struct RankedStats {
     pub tier: Tier,
     pub ranked_data: RankedData,
+    pub platform: PlatformRoute,
+    /// Position on the platform's ladder, starting at 1
+    pub ladder_position: u32,
 }
 
 #[derive(Debug)]
 pub struct ProData {
     top_leagues: TopLeagues,
-    pros: HashMap<SummonerID, Rc<Pro>>,
-    games: Vec<Rc<ProGame>>,
-    pros_in_game: HashMap<SummonerID, Rc<ProGame>>,
+    pros: Vec<Arc<Pro>>,
+    /// Maps the key of every account to the pro owning it, see `Account::key`
+    accounts: HashMap<String, Arc<Pro>>,
+    /// Maps the summoner ID of every account to its key
+    account_keys: HashMap<SummonerID, String>,
+    found: Mutex<FoundGames>,
+}
+
+/// Games found so far, shared between concurrent lookups
+#[derive(Debug, Default)]
+struct FoundGames {
+    games: Vec<Arc<ProGame>>,
+    /// Maps the key of every account in a found game to the game
+    pros_in_game: HashMap<String, Arc<ProGame>>,
 }
 
 impl RankedStats {
-    pub fn winrate(&self) -> f32 {
-        (self.ranked_data.wins as f32 / (self.ranked_data.wins + self.ranked_data.losses) as f32)
-            * 100 as f32
+    /// Winrate in percent, `None` if the account hasn't played any games
+    pub fn winrate(&self) -> Option<f32> {
+        match self.games_played() {
+            0 => None,
+            games => Some((self.ranked_data.wins as f32 / games as f32) * 100_f32),
+        }
     }
 
     pub fn games_played(&self) -> u32 {
         self.ranked_data.wins as u32 + self.ranked_data.losses as u32
     }
-}
 
-impl Pro {
-    fn new(player_name: String, team: Team, summoner_name: String, summoner_id_str: String) -> Pro {
-        let mut summoner_id = None;
-        if !summoner_id_str.is_empty() {
-            summoner_id = Some(summoner_id_str);
+    /// Short name of the tier, e.g. `C1` for challenger
+    pub fn tier_abbreviation(&self) -> &'static str {
+        match self.tier {
+            Tier::CHALLENGER => "C1",
+            Tier::GRANDMASTER => "GM",
+            Tier::MASTER => "M",
+            _ => panic!("Rank should never be below master"),
         }
+    }
+}
 
+impl Pro {
+    fn new(player_name: String, team: Team) -> Pro {
         Pro {
             player_name,
             team,
-            summoner_name,
-            summoner_id,
+            accounts: Vec::new(),
+        }
+    }
+
+    fn add_account(&mut self, account: Account) {
+        self.accounts.push(account);
+    }
+
+    pub fn name(&self) -> &str {
+        &self.player_name
+    }
+
+    /// Short name of the pro's team, e.g. `G2`
+    pub fn team(&self) -> &str {
+        &self.team.short_name
+    }
+
+    /// Full name of the pro's team, e.g. `G2 Esports`
+    pub fn team_name(&self) -> &str {
+        &self.team.full_name
+    }
+
+    /// Summoner IDs of all accounts of this pro that have one
+    pub fn summoner_ids(&self) -> impl Iterator<Item = &SummonerID> {
+        self.accounts.iter().filter_map(|a| a.summoner_id.as_ref())
+    }
+
+    /// PUUIDs of all accounts of this pro that have one
+    pub fn puuids(&self) -> impl Iterator<Item = &Puuid> {
+        self.accounts.iter().filter_map(|a| a.puuid.as_ref())
+    }
+
+    /// Keys of all accounts of this pro that have one, see `Account::key`
+    pub fn account_keys(&self) -> impl Iterator<Item = &str> {
+        self.accounts.iter().filter_map(Account::key)
+    }
+
+    /// Whether the pro is called `query`, with or without the team prefix, ignoring case
+    pub fn matches(&self, query: &str) -> bool {
+        query.eq_ignore_ascii_case(&self.player_name)
+            || query.eq_ignore_ascii_case(&self.to_string())
+    }
+
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    pub fn has_account(&self, summoner_id: &str) -> bool {
+        self.summoner_ids().any(|id| id == summoner_id)
+    }
+
+    /// Get the account of this pro with the given summoner ID
+    pub fn account(&self, summoner_id: &str) -> Option<&Account> {
+        self.accounts
+            .iter()
+            .find(|a| a.summoner_id.as_deref() == Some(summoner_id))
+    }
+}
+
+impl Account {
+    fn new(
+        riot_id: RiotId,
+        platform: PlatformRoute,
+        puuid_str: String,
+        summoner_id_str: String,
+    ) -> Account {
+        let non_empty = |s: String| if s.is_empty() { None } else { Some(s) };
+
+        Account {
+            riot_id,
+            platform,
+            puuid: non_empty(puuid_str),
+            summoner_id: non_empty(summoner_id_str),
+        }
+    }
+
+    pub fn riot_id(&self) -> &RiotId {
+        &self.riot_id
+    }
+
+    pub fn platform(&self) -> PlatformRoute {
+        self.platform
+    }
+
+    pub fn summoner_id(&self) -> Option<&str> {
+        self.summoner_id.as_deref()
+    }
+
+    /// Identifies the account in `ProData`: its PUUID, or its summoner ID if it hasn't been
+    /// synced yet, so that the account is recognised in games either way
+    fn key(&self) -> Option<&str> {
+        self.puuid.as_deref().or(self.summoner_id.as_deref())
+    }
+}
+
+/// Parses a platform such as `EUW1`, `euw` or `KR`
+pub fn parse_platform(s: &str) -> Result<PlatformRoute, String> {
+    PlatformRoute::from_str(&s.to_uppercase()).map_err(|_| format!("Unknown region \"{s}\""))
+}
+
+/// Parses an apex tier, either its name or its abbreviation such as `GM`
+pub fn parse_tier(s: &str) -> Result<Tier, String> {
+    match s.to_lowercase().as_str() {
+        "challenger" | "c1" => Ok(Tier::CHALLENGER),
+        "grandmaster" | "gm" => Ok(Tier::GRANDMASTER),
+        "master" | "m" => Ok(Tier::MASTER),
+        _ => Err(format!(
+            "Unknown tier \"{s}\", expected challenger, grandmaster or master"
+        )),
+    }
+}
+
+impl FromStr for RiotId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('#') {
+            Some((game_name, tag_line)) if !game_name.is_empty() && !tag_line.is_empty() => {
+                Ok(RiotId {
+                    game_name: game_name.to_string(),
+                    tag_line: tag_line.to_string(),
+                })
+            }
+            _ => Err(format!(
+                "Riot ID \"{s}\" is not of the form gameName#tagLine"
+            )),
         }
     }
 }
 
+impl Display for RiotId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}#{}", self.game_name, self.tag_line)
+    }
+}
+
 impl std::fmt::Display for Pro {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{} {}", self.team.short_name, self.player_name)
@@ -87,7 +264,7 @@ impl Team {
     fn new(short_name: String, full_name: String) -> Team {
         Team {
             short_name: short_name.to_uppercase(),
-            _full_name: full_name,
+            full_name,
         }
     }
 }
@@ -95,84 +272,158 @@ impl Team {
 impl ProData {
     pub async fn load(config: &Config) -> Result<ProData, Box<dyn Error>> {
         let pros = io::load_pros(config).await?;
+
+        let mut platforms: Vec<PlatformRoute> = pros
+            .iter()
+            .flat_map(|pro| pro.accounts.iter().map(|a| a.platform))
+            .collect();
+        platforms.sort();
+        platforms.dedup();
+        if platforms.is_empty() {
+            platforms.push(config.region);
+        }
+
+        let mut accounts = HashMap::new();
+        let mut account_keys = HashMap::new();
+        for pro in &pros {
+            for account in pro.accounts.iter().filter(|a| a.summoner_id.is_none()) {
+                ui::status(format!(
+                    "{}'s account {} has no summoner ID, its games can't be found. Run sync to \
+                     look it up",
+                    pro, account.riot_id
+                ));
+            }
+            for account in &pro.accounts {
+                if let Some(key) = account.key() {
+                    accounts.insert(key.to_string(), Arc::clone(pro));
+                    if let Some(summoner_id) = &account.summoner_id {
+                        account_keys.insert(summoner_id.clone(), key.to_string());
+                    }
+                }
+            }
+        }
+
+        let top_leagues = TopLeagues::get(&platforms, config).await?;
+        if let Err(e) = history::record_snapshots(config, &top_leagues, &pros) {
+            ui::status(format!("Error when saving ladder history: {e}"));
+        }
+
         Ok(ProData {
-            top_leagues: TopLeagues::get().await?,
-            pros: pros,
-            games: Vec::new(),
-            pros_in_game: HashMap::new(),
+            top_leagues,
+            pros,
+            accounts,
+            account_keys,
+            found: Mutex::new(FoundGames::default()),
         })
     }
 
-    /// Gets the LP for a summoner (in master and above)
-    /// # Parameter
-    /// `summoner_id` - the summoner ID of the summoner
-    /// # Returns
-    /// `RiotApiError` if getting the league failed
-    /// `Ok(Some(lp))` if summoner is above master
-    /// `Ok(None)` if summoner isn't ranked or isn't above master
-    pub fn get_lp(&self, summoner_id: &str) -> Option<i32> {
-        self.top_leagues.get_lp(summoner_id)
-    }
-
     pub fn ranked_stats(&self, summoner_id: &str) -> Option<RankedStats> {
         self.top_leagues.get_rank(summoner_id)
     }
 
-    pub fn pro_leaderboard(&self) -> Vec<(Rc<Pro>, RankedStats)> {
+    /// Gets the ranked stats of every ranked account of a pro
+    /// # Parameter
+    /// `pro` - the pro to get ranked stats for
+    /// # Returns
+    /// The ranked stats of each account that is master or above, sorted by LP with the best
+    /// account first
+    pub fn ranked_accounts(&self, pro: &Pro) -> Vec<RankedStats> {
+        let mut result: Vec<RankedStats> = pro
+            .summoner_ids()
+            .filter_map(|id| self.ranked_stats(id))
+            .collect();
+
+        result.sort_by_key(|r| std::cmp::Reverse(r.ranked_data.league_points));
+        result
+    }
+
+    /// Gets every ranked pro together with the ranked stats of their best account, sorted by LP
+    /// and then by wins
+    pub fn pro_leaderboard(&self) -> Vec<(Arc<Pro>, RankedStats)> {
         let mut result = Vec::new();
         for pro in self.get_pros() {
-            let rank = match self.ranked_stats(&pro.summoner_id.as_ref().unwrap()) {
+            let rank = match self.ranked_accounts(&pro).into_iter().next() {
                 Some(r) => r,
                 None => continue,
             };
             result.push((pro, rank));
         }
 
-        result.sort_by_key(|&(_, ref r)| r.ranked_data.league_points);
-        result.reverse();
+        result.sort_by_key(|(_, r)| {
+            std::cmp::Reverse((r.ranked_data.league_points, r.ranked_data.wins))
+        });
         result
     }
 
     // TODO: find way to return Vec<&Pro>
-    pub fn get_pros(&self) -> Vec<Rc<Pro>> {
-        let mut result = Vec::new();
-        for (_, val) in &self.pros {
-            result.push(Rc::clone(&val));
-        }
-        result
+    pub fn get_pros(&self) -> Vec<Arc<Pro>> {
+        self.pros.iter().map(Arc::clone).collect()
     }
 
     pub fn is_in_game(&self, pro: &Pro) -> bool {
-        let summoner_id = pro.summoner_id.clone().unwrap_or("".to_string());
-        self.pros_in_game.contains_key(summoner_id.as_str())
+        let found = self.found.lock().expect("lock should not be poisoned");
+        pro.account_keys()
+            .any(|key| found.pros_in_game.contains_key(key))
     }
 
+    /// Looks for a ranked game on any of the pro's accounts. Takes `&self` so that several lookups
+    /// can run concurrently, riven's rate limiter queues the requests.
+    /// # Returns
+    /// - `Ok(Some(game))` for the first account found in a ranked game
+    /// - `Ok(None)` if none of the accounts are in a ranked game. Accounts without a summoner ID
+    ///   are skipped
     pub async fn fetch_game(
-        &mut self,
+        &self,
         pro: &Pro,
-    ) -> std::result::Result<Option<Rc<ProGame>>, RiotApiError> {
-        let summoner_id: &SummonerID = match &pro.summoner_id {
-            Some(id) => id,
-            None => {
-                eprintln!("{} has no summoner ID, can't find game. Quitting.", pro);
-                std::process::exit(1);
-            }
-        };
-
+    ) -> std::result::Result<Option<Arc<ProGame>>, api::Error> {
         /* If this pro already is in a found game then we return that game instantly */
-        if let Some(game) = self
-            .pros_in_game
-            .get(pro.summoner_id.clone().unwrap().as_str())
         {
-            dbg!("Found pro in game");
-            return Ok(Some(Rc::clone(&game)));
+            let found = self.found.lock().expect("lock should not be poisoned");
+            for key in pro.account_keys() {
+                if let Some(game) = found.pros_in_game.get(key) {
+                    return Ok(Some(Arc::clone(game)));
+                }
+            }
         }
 
-        let game_info = match RIOT_API
-            .spectator_v4()
-            .get_current_game_info_by_summoner(PlatformRoute::EUW1, summoner_id)
-            .await?
-        {
+        for account in &pro.accounts {
+            let summoner_id = match &account.summoner_id {
+                Some(id) => id,
+                None => continue,
+            };
+            if let Some(game) = self
+                .fetch_account_game(account.platform, summoner_id)
+                .await?
+            {
+                return Ok(Some(game));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets the game an account is currently in, ranked or not
+    async fn spectate(
+        platform: PlatformRoute,
+        summoner_id: &str,
+    ) -> std::result::Result<Option<CurrentGameInfo>, api::Error> {
+        api::request(
+            platform.into(),
+            &format!("/lol/spectator/v4/active-games/by-summoner/{summoner_id}"),
+            RIOT_API
+                .spectator_v4()
+                .get_current_game_info_by_summoner(platform, summoner_id),
+        )
+        .await
+    }
+
+    /// Looks for a ranked game for a single account on its own platform
+    async fn fetch_account_game(
+        &self,
+        platform: PlatformRoute,
+        summoner_id: &str,
+    ) -> std::result::Result<Option<Arc<ProGame>>, api::Error> {
+        let game_info = match Self::spectate(platform, summoner_id).await? {
             Some(g) => g,
             None => return Ok(None),
         };
@@ -184,56 +435,152 @@ impl ProData {
             }
         }
 
+        /* Another lookup may have found this game while we were waiting for the API */
+        let mut found = self.found.lock().expect("lock should not be poisoned");
+        if let Some(game) = found
+            .games
+            .iter()
+            .find(|g| g.game_info.game_id == game_info.game_id)
+        {
+            return Ok(Some(Arc::clone(game)));
+        }
+
         let pro_players = self.find_pros_in_game(&game_info);
         let mut players = Vec::new();
         for participant in &game_info.participants {
-            let p = Player::new(participant.summoner_id.as_str(), participant.clone(), &self)
+            let p = Player::new(participant.summoner_id.as_str(), participant.clone(), self)
                 .expect("Couldnt create Player");
             players.push(p);
         }
 
-        let game = Rc::new(ProGame {
+        let game = Arc::new(ProGame {
             players,
             game_info,
-            pro_players: pro_players,
+            pro_players,
         });
 
-        /* Insert each pro player in this game into the hashmap of pro_players that are in game. */
-        for pro_player in &game.pro_players {
-            self.pros_in_game
-                .insert(pro_player.summoner_id.clone().unwrap(), Rc::clone(&game));
+        /* Insert the account of each pro player in this game into the hashmap of pro_players that
+         * are in game. */
+        for participant in &game.game_info.participants {
+            if let Some(key) = self.account_keys.get(&participant.summoner_id) {
+                found.pros_in_game.insert(key.clone(), Arc::clone(&game));
+            }
         }
 
-        let game_clone = Rc::clone(&game);
-        self.games.push(game);
+        let game_clone = Arc::clone(&game);
+        found.games.push(game);
 
         Ok(Some(game_clone))
     }
 
-    fn find_pros_in_game(&self, game_info: &CurrentGameInfo) -> Vec<Rc<Pro>> {
-        let mut pros_in_this_game: Vec<Rc<Pro>> = Vec::new();
+    /// Resolves every participant in the game back to the pro owning the account
+    fn find_pros_in_game(&self, game_info: &CurrentGameInfo) -> Vec<Arc<Pro>> {
+        let mut pros_in_this_game: Vec<Arc<Pro>> = Vec::new();
         let summoners: &Vec<CurrentGameParticipant> = &game_info.participants;
         for summoner in summoners {
-            let summoner_id = &summoner.summoner_id;
+            if let Some(pro) = self
+                .account_keys
+                .get(&summoner.summoner_id)
+                .and_then(|key| self.accounts.get(key))
+            {
+                pros_in_this_game.push(Arc::clone(pro));
+            }
+        }
 
-            match self.pros.get(summoner_id) {
-                Some(pro) => {
-                    pros_in_this_game.push(Rc::clone(pro));
-                }
+        pros_in_this_game
+    }
+
+    /// Checks whether the games found so far are still being played, by looking up one pro
+    /// account in each game, and forgets the ones that have ended so that their pros are looked
+    /// up again
+    /// # Returns
+    /// The games that have ended. Games that couldn't be checked are assumed to still be played
+    pub async fn remove_finished_games(&self) -> Vec<Arc<ProGame>> {
+        let mut finished = Vec::new();
 
-                None => {}
+        for game in self.games() {
+            let platform = match game.platform() {
+                Some(p) => p,
+                None => continue,
+            };
+            let summoner_id = match game
+                .game_info
+                .participants
+                .iter()
+                .find(|p| self.account_keys.contains_key(&p.summoner_id))
+            {
+                Some(p) => &p.summoner_id,
+                None => continue,
+            };
+
+            match Self::spectate(platform, summoner_id).await {
+                Ok(Some(info)) if info.game_id == game.game_id() => continue,
+                Ok(_) => finished.push(game),
+                Err(e) => ui::status(format!("Error when checking game {}: {e}", game.game_id())),
             }
         }
 
-        pros_in_this_game
+        let mut found = self.found.lock().expect("lock should not be poisoned");
+        for game in &finished {
+            found.games.retain(|g| g.game_id() != game.game_id());
+            found
+                .pros_in_game
+                .retain(|_, g| g.game_id() != game.game_id());
+        }
+
+        finished
+    }
+
+    /// Fetches the ladders again if any of them is older than the ladder TTL, recording a ladder
+    /// snapshot like when loading. Games found before keep the ranks they were found with.
+    /// # Returns
+    /// Whether the ladders were fetched again
+    pub async fn refresh_top_leagues(&mut self, config: &Config) -> Result<bool, Box<dyn Error>> {
+        let now = chrono::Utc::now().timestamp();
+        let ttl = config.ladder_ttl.as_secs() as i64;
+        if self
+            .top_leagues
+            .fetched_at
+            .values()
+            .all(|&fetched_at| now - fetched_at < ttl)
+        {
+            return Ok(false);
+        }
+
+        let mut platforms: Vec<PlatformRoute> =
+            self.top_leagues.fetched_at.keys().copied().collect();
+        platforms.sort();
+        self.top_leagues = TopLeagues::get(&platforms, config).await?;
+        if let Err(e) = history::record_snapshots(config, &self.top_leagues, &self.pros) {
+            ui::status(format!("Error when saving ladder history: {e}"));
+        }
+
+        Ok(true)
+    }
+
+    /// Gets every game found so far
+    pub fn games(&self) -> Vec<Arc<ProGame>> {
+        self.found
+            .lock()
+            .expect("lock should not be poisoned")
+            .games
+            .clone()
     }
 
     pub fn games_count(&self) -> usize {
-        self.games.len()
+        self.found
+            .lock()
+            .expect("lock should not be poisoned")
+            .games
+            .len()
     }
 
     pub fn pros_in_game_count(&self) -> usize {
-        self.pros_in_game.len()
+        self.found
+            .lock()
+            .expect("lock should not be poisoned")
+            .pros_in_game
+            .len()
     }
 
     pub fn pros_count(&self) -> usize {
@@ -243,20 +590,14 @@ impl ProData {
 
 impl Display for RankedStats {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let tier_str = match self.tier {
-            Tier::CHALLENGER => "C1",
-            Tier::GRANDMASTER => "GM",
-            Tier::MASTER => "M",
-            _ => panic!("Rank should never be below master"),
-        };
-
         write!(
             f,
-            "{} {}LP {}gp {:.1}%",
-            tier_str,
+            "{} {}LP {}gp {}",
+            self.tier_abbreviation(),
             self.ranked_data.league_points,
             self.games_played(),
             self.winrate()
+                .map_or("-".to_string(), |winrate| format!("{winrate:.1}%"))
         )
     }
 }
@@ -0,0 +1,545 @@
+use super::history::Snapshot;
+use super::io::Row;
+use super::tracker::{MatchResult, TrackedGame};
+use super::*;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// Version of the schema created by `SCHEMA`, stored in `PRAGMA user_version`
+const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS teams (
+        short_name TEXT PRIMARY KEY,
+        full_name TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS pros (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        team TEXT NOT NULL REFERENCES teams(short_name),
+        UNIQUE (team, name)
+    );
+    CREATE TABLE IF NOT EXISTS accounts (
+        id INTEGER PRIMARY KEY,
+        pro_id INTEGER NOT NULL REFERENCES pros(id) ON DELETE CASCADE,
+        riot_id TEXT NOT NULL,
+        region TEXT NOT NULL DEFAULT '',
+        puuid TEXT NOT NULL DEFAULT '',
+        summoner_id TEXT NOT NULL DEFAULT ''
+    );
+    CREATE TABLE IF NOT EXISTS ladder_snapshots (
+        timestamp INTEGER NOT NULL,
+        puuid TEXT NOT NULL,
+        riot_id TEXT NOT NULL,
+        platform TEXT NOT NULL,
+        tier TEXT NOT NULL,
+        league_points INTEGER NOT NULL,
+        wins INTEGER NOT NULL,
+        losses INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS ladder_snapshots_puuid ON ladder_snapshots (puuid, timestamp);
+    CREATE TABLE IF NOT EXISTS tracked_games (
+        game_id INTEGER NOT NULL,
+        platform TEXT NOT NULL,
+        first_seen INTEGER NOT NULL,
+        pros TEXT NOT NULL,
+        PRIMARY KEY (game_id, platform)
+    );
+    CREATE TABLE IF NOT EXISTS match_results (
+        game_id INTEGER NOT NULL,
+        platform TEXT NOT NULL,
+        game_end INTEGER NOT NULL,
+        puuid TEXT NOT NULL,
+        riot_id TEXT NOT NULL,
+        champion TEXT NOT NULL,
+        win INTEGER NOT NULL,
+        kills INTEGER NOT NULL,
+        deaths INTEGER NOT NULL,
+        assists INTEGER NOT NULL,
+        duration INTEGER NOT NULL,
+        lp_change INTEGER,
+        PRIMARY KEY (game_id, platform, puuid)
+    );
+";
+
+/// Whether a path looks like a SQLite database judging by its extension
+pub fn is_database_path(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("db" | "sqlite" | "sqlite3")
+    )
+}
+
+/// Opens the database at the pro file path, creating the schema if needed
+pub(super) fn open(config: &Config) -> Result<Connection, Box<dyn Error>> {
+    let connection = Connection::open(&config.pro_file_path)?;
+    /* WAL lets other processes read while we write */
+    connection.pragma_update(None, "journal_mode", "WAL")?;
+    connection.pragma_update(None, "foreign_keys", "ON")?;
+
+    let version: i32 = connection.pragma_query_value(None, "user_version", |r| r.get(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Database schema version {version} is newer than the supported version {SCHEMA_VERSION}"
+        )
+        .into());
+    }
+    connection.execute_batch(SCHEMA)?;
+    connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+    Ok(connection)
+}
+
+/// Loads every account together with its ID, ordered like they were inserted
+pub(super) fn load_rows(connection: &Connection) -> Result<Vec<(i64, Row)>, Box<dyn Error>> {
+    let mut statement = connection.prepare(
+        "SELECT a.id, p.name, t.short_name, t.full_name, a.riot_id, a.region, a.puuid, a.summoner_id
+         FROM accounts a
+         JOIN pros p ON a.pro_id = p.id
+         JOIN teams t ON p.team = t.short_name
+         ORDER BY p.id, a.id",
+    )?;
+    let rows = statement.query_map([], |r| {
+        Ok((
+            r.get(0)?,
+            Row {
+                pro_name: r.get(1)?,
+                short_team: r.get(2)?,
+                long_team: r.get(3)?,
+                riot_id: r.get(4)?,
+                region: r.get(5)?,
+                puuid: r.get(6)?,
+                summoner_id: r.get(7)?,
+            },
+        ))
+    })?;
+
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Inserts an account, creating its team and pro if they don't exist yet
+pub(super) fn insert_row(connection: &Connection, row: &Row) -> Result<(), Box<dyn Error>> {
+    let short_team = row.short_team.to_uppercase();
+    connection.execute(
+        "INSERT INTO teams (short_name, full_name) VALUES (?1, ?2)
+         ON CONFLICT (short_name) DO UPDATE SET full_name = excluded.full_name",
+        params![short_team, row.long_team],
+    )?;
+    connection.execute(
+        "INSERT OR IGNORE INTO pros (name, team) VALUES (?1, ?2)",
+        params![row.pro_name, short_team],
+    )?;
+    let pro_id: i64 = connection.query_row(
+        "SELECT id FROM pros WHERE name = ?1 AND team = ?2",
+        params![row.pro_name, short_team],
+        |r| r.get(0),
+    )?;
+    connection.execute(
+        "INSERT INTO accounts (pro_id, riot_id, region, puuid, summoner_id)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![pro_id, row.riot_id, row.region, row.puuid, row.summoner_id],
+    )?;
+
+    Ok(())
+}
+
+pub(super) fn update_account(
+    connection: &Connection,
+    account_id: i64,
+    riot_id: &str,
+    puuid: &str,
+    summoner_id: &str,
+) -> Result<(), Box<dyn Error>> {
+    connection.execute(
+        "UPDATE accounts SET riot_id = ?2, puuid = ?3, summoner_id = ?4 WHERE id = ?1",
+        params![account_id, riot_id, puuid, summoner_id],
+    )?;
+    Ok(())
+}
+
+/// Deletes accounts, and the pros and teams left without accounts
+pub(super) fn delete_accounts(
+    connection: &mut Connection,
+    account_ids: &[i64],
+) -> Result<(), Box<dyn Error>> {
+    let transaction = connection.transaction()?;
+    for id in account_ids {
+        transaction.execute("DELETE FROM accounts WHERE id = ?1", params![id])?;
+    }
+    delete_orphans(&transaction)?;
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Renames a pro or moves them to another team
+/// # Parameters
+/// `old`, `new`: team and name of the pro before and after the change
+/// `team_name`: full name of the new team
+pub(super) fn update_pro(
+    connection: &mut Connection,
+    old: (&str, &str),
+    new: (&str, &str),
+    team_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let transaction = connection.transaction()?;
+    transaction.execute(
+        "INSERT INTO teams (short_name, full_name) VALUES (?1, ?2)
+         ON CONFLICT (short_name) DO UPDATE SET full_name = excluded.full_name",
+        params![new.0, team_name],
+    )?;
+    transaction.execute(
+        "UPDATE pros SET team = ?3, name = ?4 WHERE team = ?1 AND name = ?2",
+        params![old.0, old.1, new.0, new.1],
+    )?;
+    delete_orphans(&transaction)?;
+    transaction.commit()?;
+    Ok(())
+}
+
+fn delete_orphans(connection: &Connection) -> Result<(), Box<dyn Error>> {
+    connection.execute(
+        "DELETE FROM pros WHERE id NOT IN (SELECT pro_id FROM accounts)",
+        [],
+    )?;
+    connection.execute(
+        "DELETE FROM teams WHERE short_name NOT IN (SELECT team FROM pros)",
+        [],
+    )?;
+    Ok(())
+}
+
+pub(super) fn append_snapshots(
+    connection: &mut Connection,
+    snapshots: &[Snapshot],
+) -> Result<(), Box<dyn Error>> {
+    let transaction = connection.transaction()?;
+    insert_snapshots(&transaction, snapshots)?;
+    transaction.commit()?;
+    Ok(())
+}
+
+fn insert_snapshots(connection: &Connection, snapshots: &[Snapshot]) -> Result<(), Box<dyn Error>> {
+    for s in snapshots {
+        connection.execute(
+            "INSERT INTO ladder_snapshots
+             (timestamp, puuid, riot_id, platform, tier, league_points, wins, losses)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                s.timestamp,
+                s.puuid,
+                s.riot_id,
+                s.platform,
+                s.tier,
+                s.league_points,
+                s.wins,
+                s.losses
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+pub(super) fn load_snapshots(connection: &Connection) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+    let mut statement = connection.prepare(
+        "SELECT timestamp, puuid, riot_id, platform, tier, league_points, wins, losses
+         FROM ladder_snapshots ORDER BY timestamp",
+    )?;
+    let snapshots = statement.query_map([], |r| {
+        Ok(Snapshot {
+            timestamp: r.get(0)?,
+            puuid: r.get(1)?,
+            riot_id: r.get(2)?,
+            platform: r.get(3)?,
+            tier: r.get(4)?,
+            league_points: r.get(5)?,
+            wins: r.get(6)?,
+            losses: r.get(7)?,
+        })
+    })?;
+
+    Ok(snapshots.collect::<Result<_, _>>()?)
+}
+
+pub(super) fn load_tracked_games(
+    connection: &Connection,
+) -> Result<Vec<TrackedGame>, Box<dyn Error>> {
+    let mut statement =
+        connection.prepare("SELECT game_id, platform, first_seen, pros FROM tracked_games")?;
+    let rows = statement.query_map([], |r| {
+        Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get::<_, String>(3)?))
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        let (game_id, platform, first_seen, pros) = row?;
+        result.push(TrackedGame {
+            game_id,
+            platform,
+            first_seen,
+            pros: serde_json::from_str(&pros)?,
+        });
+    }
+    Ok(result)
+}
+
+/// Replaces all tracked games with the given ones
+pub(super) fn save_tracked_games(
+    connection: &mut Connection,
+    games: &[TrackedGame],
+) -> Result<(), Box<dyn Error>> {
+    let transaction = connection.transaction()?;
+    replace_tracked_games(&transaction, games)?;
+    transaction.commit()?;
+    Ok(())
+}
+
+fn replace_tracked_games(
+    connection: &Connection,
+    games: &[TrackedGame],
+) -> Result<(), Box<dyn Error>> {
+    connection.execute("DELETE FROM tracked_games", [])?;
+    for game in games {
+        connection.execute(
+            "INSERT INTO tracked_games (game_id, platform, first_seen, pros)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                game.game_id,
+                game.platform,
+                game.first_seen,
+                serde_json::to_string(&game.pros)?
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Appends results and replaces all tracked games with the ones still pending, in one transaction
+/// so that resolved games are never tracked and resolved again
+pub(super) fn record_results(
+    connection: &mut Connection,
+    results: &[MatchResult],
+    pending: &[TrackedGame],
+) -> Result<(), Box<dyn Error>> {
+    let transaction = connection.transaction()?;
+    insert_results(&transaction, results)?;
+    replace_tracked_games(&transaction, pending)?;
+    transaction.commit()?;
+    Ok(())
+}
+
+fn insert_results(connection: &Connection, results: &[MatchResult]) -> Result<(), Box<dyn Error>> {
+    for r in results {
+        connection.execute(
+            "INSERT OR REPLACE INTO match_results
+             (game_id, platform, game_end, puuid, riot_id, champion, win, kills, deaths, assists,
+              duration, lp_change)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                r.game_id,
+                r.platform,
+                r.game_end,
+                r.puuid,
+                r.riot_id,
+                r.champion,
+                r.win,
+                r.kills,
+                r.deaths,
+                r.assists,
+                r.duration,
+                r.lp_change
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+pub(super) fn load_results(connection: &Connection) -> Result<Vec<MatchResult>, Box<dyn Error>> {
+    let mut statement = connection.prepare(
+        "SELECT game_id, platform, game_end, puuid, riot_id, champion, win, kills, deaths, assists,
+                duration, lp_change
+         FROM match_results ORDER BY game_end",
+    )?;
+    let results = statement.query_map([], |r| {
+        Ok(MatchResult {
+            game_id: r.get(0)?,
+            platform: r.get(1)?,
+            game_end: r.get(2)?,
+            puuid: r.get(3)?,
+            riot_id: r.get(4)?,
+            champion: r.get(5)?,
+            win: r.get(6)?,
+            kills: r.get(7)?,
+            deaths: r.get(8)?,
+            assists: r.get(9)?,
+            duration: r.get(10)?,
+            lp_change: r.get(11)?,
+        })
+    })?;
+
+    Ok(results.collect::<Result<_, _>>()?)
+}
+
+/// Imports pros from a CSV file and any ladder and match history from the data directory into the
+/// database, all in one transaction so that a failed import leaves the database untouched
+/// # Returns
+/// The number of accounts, snapshots and match results imported
+pub fn migrate_csv(
+    config: &Config,
+    csv_config: &Config,
+) -> Result<(usize, usize, usize), Box<dyn Error>> {
+    let rows = io::read_csv_rows(&csv_config.pro_file_path)?;
+    let snapshots = history::load_all_snapshots(csv_config)?;
+    let results = tracker::load_all_results(csv_config)?;
+    let tracked = tracker::load_tracked_games(csv_config)?;
+
+    let mut connection = open(config)?;
+    let transaction = connection.transaction()?;
+    let existing: Option<i64> = transaction
+        .query_row("SELECT id FROM accounts LIMIT 1", [], |r| r.get(0))
+        .optional()?;
+    if existing.is_some() {
+        return Err(format!("{} already contains pros", config.pro_file_path).into());
+    }
+
+    for row in &rows {
+        insert_row(&transaction, row)?;
+    }
+    insert_snapshots(&transaction, &snapshots)?;
+    insert_results(&transaction, &results)?;
+    replace_tracked_games(&transaction, &tracked)?;
+    transaction.commit()?;
+
+    Ok((rows.len(), snapshots.len(), results.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use crate::Storage;
+
+    fn write_csv_storage(dir: &Path) {
+        std::fs::write(
+            dir.join("pros.csv"),
+            "pro_name,short_team,long_team,riot_id,region,puuid,summoner_id\n\
+             Faker,T1,T1,Hide on bush#KR1,KR,puuid-faker,sid-faker\n\
+             Faker,T1,T1,Faker2#KR1,KR,,\n\
+             Caps,G2,G2 Esports,Caps#EUW,EUW1,puuid-caps,sid-caps\n",
+        )
+        .expect("should write pros");
+        std::fs::create_dir_all(dir.join("data")).expect("should create data directory");
+        std::fs::write(
+            dir.join("data/ladder_history.csv"),
+            "timestamp,puuid,riot_id,platform,tier,league_points,wins,losses\n\
+             100,puuid-faker,Hide on bush#KR1,KR,CHALLENGER,1200,100,80\n\
+             200,puuid-caps,Caps#EUW,EUW1,GRANDMASTER,700,60,50\n",
+        )
+        .expect("should write ladder history");
+        std::fs::write(
+            dir.join("data/match_history.csv"),
+            "game_id,platform,game_end,puuid,riot_id,champion,win,kills,deaths,assists,\
+             duration,lp_change\n\
+             1,KR,150,puuid-faker,Hide on bush#KR1,Ahri,true,5,1,7,1800,21\n",
+        )
+        .expect("should write match history");
+        std::fs::write(
+            dir.join("data/tracked_games.json"),
+            r#"[{"game_id":2,"platform":"EUW1","first_seen":250,"pros":[
+                {"puuid":"puuid-caps","riot_id":"Caps#EUW","league_points":700}]}]"#,
+        )
+        .expect("should write tracked games");
+    }
+
+    #[tokio::test]
+    async fn migrate_csv_imports_pros_and_history() {
+        let dir = test_util::temp_dir("migrate_csv_imports_pros_and_history");
+        write_csv_storage(&dir);
+        let csv_config = test_util::config(&dir, &[]);
+        let db_path = dir.join("pros.db").to_string_lossy().to_string();
+        let config = test_util::config(&dir, &["-p", &db_path]);
+        assert_eq!(config.storage, Storage::Sqlite);
+
+        let counts = migrate_csv(&config, &csv_config).expect("migration should succeed");
+        assert_eq!(counts, (3, 2, 1));
+
+        let pros = io::load_pros(&config).await.expect("pros should load");
+        let accounts: Vec<(String, String)> = pros
+            .iter()
+            .flat_map(|pro| pro.accounts().iter().map(move |a| (pro, a)))
+            .map(|(pro, a)| (pro.name().to_string(), a.riot_id().to_string()))
+            .collect();
+        assert_eq!(
+            accounts,
+            [
+                ("Faker".to_string(), "Hide on bush#KR1".to_string()),
+                ("Faker".to_string(), "Faker2#KR1".to_string()),
+                ("Caps".to_string(), "Caps#EUW".to_string()),
+            ]
+        );
+
+        let snapshots = history::load_all_snapshots(&config).expect("snapshots should load");
+        let snapshots: Vec<(i64, &str, i32)> = snapshots
+            .iter()
+            .map(|s| (s.timestamp, s.puuid.as_str(), s.league_points))
+            .collect();
+        assert_eq!(
+            snapshots,
+            [(100, "puuid-faker", 1200), (200, "puuid-caps", 700)]
+        );
+
+        let results = tracker::load_all_results(&config).expect("results should load");
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            (
+                results[0].game_id,
+                results[0].champion.as_str(),
+                results[0].lp_change
+            ),
+            (1, "Ahri", Some(21))
+        );
+
+        let tracked = tracker::load_tracked_games(&config).expect("tracked games should load");
+        assert_eq!(tracked.len(), 1);
+        assert_eq!(tracked[0].game_id, 2);
+    }
+
+    #[test]
+    fn migrate_csv_refuses_database_with_pros() {
+        let dir = test_util::temp_dir("migrate_csv_refuses_database_with_pros");
+        write_csv_storage(&dir);
+        let csv_config = test_util::config(&dir, &[]);
+        let db_path = dir.join("pros.db").to_string_lossy().to_string();
+        let config = test_util::config(&dir, &["-p", &db_path]);
+
+        migrate_csv(&config, &csv_config).expect("first migration should succeed");
+        let e = migrate_csv(&config, &csv_config)
+            .expect_err("second migration should fail")
+            .to_string();
+        assert_eq!(e, format!("{db_path} already contains pros"));
+
+        let connection = open(&config).expect("database should open");
+        let accounts: i64 = connection
+            .query_row("SELECT COUNT(*) FROM accounts", [], |r| r.get(0))
+            .expect("accounts should be counted");
+        assert_eq!(accounts, 3);
+    }
+
+    #[test]
+    fn open_refuses_newer_schema() {
+        let dir = test_util::temp_dir("open_refuses_newer_schema");
+        let db_path = dir.join("pros.db").to_string_lossy().to_string();
+        let config = test_util::config(&dir, &["-p", &db_path]);
+
+        open(&config)
+            .expect("database should open")
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .expect("version should be set");
+        let e = open(&config)
+            .expect_err("newer schema should be refused")
+            .to_string();
+        assert_eq!(
+            e,
+            "Database schema version 2 is newer than the supported version 1"
+        );
+    }
+}
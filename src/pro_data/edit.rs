@@ -0,0 +1,231 @@
+use super::io::{CsvTable, Row, CSV_HEADER};
+use super::*;
+use crate::Storage;
+
+/// Changes to apply to a pro, `None` fields are left as they are
+pub struct ProEdit {
+    pub name: Option<String>,
+    pub team: Option<String>,
+    /// Full name of the pro's (new) team, applied to every pro of that team
+    pub team_name: Option<String>,
+}
+
+/// Finds the one pro matching a query such as "Caps" or "G2 Caps"
+pub fn find_pro(pros: &[Arc<Pro>], query: &str) -> Result<Arc<Pro>, Box<dyn Error>> {
+    let mut found = pros.iter().filter(|p| p.matches(query));
+    match (found.next(), found.next()) {
+        (Some(pro), None) => Ok(pro.clone()),
+        (Some(_), Some(_)) => Err(format!(
+            "More than one pro is called {query}, prefix the name with the team"
+        )
+        .into()),
+        (None, _) => Err(format!("No pro called {query} in the database").into()),
+    }
+}
+
+fn is_pro_row(row_team: &str, row_name: &str, pro: &Pro) -> bool {
+    row_team.eq_ignore_ascii_case(&pro.team.short_name) && row_name == pro.player_name
+}
+
+/// Gets the full name of a team from the rows of its pros
+fn team_full_name(rows: &[Row], short_team: &str) -> Option<String> {
+    rows.iter()
+        .find(|r| r.short_team.eq_ignore_ascii_case(short_team))
+        .map(|r| r.long_team.clone())
+}
+
+fn load_rows(config: &Config) -> Result<Vec<Row>, Box<dyn Error>> {
+    match config.storage {
+        Storage::Csv => io::read_csv_rows(&config.pro_file_path),
+        Storage::Sqlite => Ok(db::load_rows(&db::open(config)?)?
+            .into_iter()
+            .map(|(_, row)| row)
+            .collect()),
+    }
+}
+
+/// Adds an account to a pro, creating the pro if they don't exist yet. The PUUID and summoner ID
+/// are resolved right away, so nothing is written if the account can't be found.
+/// # Parameters
+/// `region`: platform of the account, `None` for the configured region
+/// `team_name`: full name of the team, only needed if the team is new
+pub async fn add_account(
+    config: &Config,
+    team: &str,
+    name: &str,
+    riot_id: &RiotId,
+    region: Option<PlatformRoute>,
+    team_name: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let rows = load_rows(config)?;
+    let platform = region.unwrap_or(config.region);
+    let team = team.to_uppercase();
+
+    let duplicate = rows.iter().any(|r| {
+        r.riot_id.eq_ignore_ascii_case(&riot_id.to_string())
+            && io::row_platform(r, config).is_ok_and(|p| p == platform)
+    });
+    if duplicate {
+        return Err(format!("{riot_id} on {platform} is already in the database").into());
+    }
+    let team_name = match team_name.map(String::from).or(team_full_name(&rows, &team)) {
+        Some(t) => t,
+        None => {
+            return Err(format!("{team} is a new team, give its full name with --team-name").into())
+        }
+    };
+
+    let puuid = io::get_puuid(platform, riot_id).await?;
+    let summoner_id = io::get_summoner_id(platform, &puuid).await?;
+    let row = Row {
+        pro_name: name.to_string(),
+        short_team: team,
+        long_team: team_name,
+        riot_id: riot_id.to_string(),
+        region: region.map(|r| r.to_string()).unwrap_or_default(),
+        puuid,
+        summoner_id,
+    };
+
+    match config.storage {
+        Storage::Csv => {
+            let mut table = CsvTable::read(&config.pro_file_path)?;
+            let fields = [
+                &row.pro_name,
+                &row.short_team,
+                &row.long_team,
+                &row.riot_id,
+                &row.region,
+                &row.puuid,
+                &row.summoner_id,
+            ];
+            let mut record = vec![String::new(); table.headers.len()];
+            for (header, field) in CSV_HEADER.iter().zip(fields) {
+                record[table.column(header)] = field.clone();
+            }
+
+            /* Keep the accounts of a pro together */
+            let (team_column, name_column) = (table.column("short_team"), table.column("pro_name"));
+            let position = table
+                .records
+                .iter()
+                .rposition(|r| {
+                    r[team_column].eq_ignore_ascii_case(&row.short_team)
+                        && r[name_column] == row.pro_name
+                })
+                .map_or(table.records.len(), |i| i + 1);
+            table.records.insert(position, record);
+            table.write(&config.pro_file_path)?;
+        }
+        Storage::Sqlite => db::insert_row(&db::open(config)?, &row)?,
+    }
+
+    Ok(())
+}
+
+/// Removes a pro, or only one of their accounts
+/// # Returns
+/// The number of accounts removed
+pub fn remove(
+    config: &Config,
+    pro: &Pro,
+    riot_id: Option<&RiotId>,
+) -> Result<usize, Box<dyn Error>> {
+    let is_removed_account = |account_riot_id: &str| {
+        riot_id.is_none_or(|r| account_riot_id.eq_ignore_ascii_case(&r.to_string()))
+    };
+
+    let removed = match config.storage {
+        Storage::Csv => {
+            let mut table = CsvTable::read(&config.pro_file_path)?;
+            let (team_column, name_column, riot_id_column) = (
+                table.column("short_team"),
+                table.column("pro_name"),
+                table.column("riot_id"),
+            );
+            let count = table.records.len();
+            table.records.retain(|r| {
+                !(is_pro_row(&r[team_column], &r[name_column], pro)
+                    && is_removed_account(&r[riot_id_column]))
+            });
+            let removed = count - table.records.len();
+            if removed > 0 {
+                table.write(&config.pro_file_path)?;
+            }
+            removed
+        }
+        Storage::Sqlite => {
+            let mut connection = db::open(config)?;
+            let ids: Vec<i64> = db::load_rows(&connection)?
+                .into_iter()
+                .filter(|(_, r)| {
+                    is_pro_row(&r.short_team, &r.pro_name, pro) && is_removed_account(&r.riot_id)
+                })
+                .map(|(id, _)| id)
+                .collect();
+            db::delete_accounts(&mut connection, &ids)?;
+            ids.len()
+        }
+    };
+
+    if removed == 0 {
+        if let Some(riot_id) = riot_id {
+            return Err(format!("{pro} has no account {riot_id}").into());
+        }
+    }
+    Ok(removed)
+}
+
+/// Changes the name or team of a pro
+pub fn edit(config: &Config, pro: &Pro, changes: &ProEdit) -> Result<(), Box<dyn Error>> {
+    let rows = load_rows(config)?;
+    let name = changes.name.as_ref().unwrap_or(&pro.player_name);
+    let team = changes
+        .team
+        .as_ref()
+        .unwrap_or(&pro.team.short_name)
+        .to_uppercase();
+
+    let renamed = *name != pro.player_name || team != pro.team.short_name;
+    let exists = rows
+        .iter()
+        .any(|r| r.short_team.eq_ignore_ascii_case(&team) && r.pro_name == *name);
+    if renamed && exists {
+        return Err(format!("{team} {name} is already in the database").into());
+    }
+    let team_name = match changes.team_name.clone().or(team_full_name(&rows, &team)) {
+        Some(t) => t,
+        None => {
+            return Err(format!("{team} is a new team, give its full name with --team-name").into())
+        }
+    };
+
+    match config.storage {
+        Storage::Csv => {
+            let mut table = CsvTable::read(&config.pro_file_path)?;
+            let (team_column, name_column, team_name_column) = (
+                table.column("short_team"),
+                table.column("pro_name"),
+                table.column("long_team"),
+            );
+            for record in &mut table.records {
+                if is_pro_row(&record[team_column], &record[name_column], pro) {
+                    record[name_column] = name.clone();
+                    record[team_column] = team.clone();
+                }
+                if record[team_column].eq_ignore_ascii_case(&team) {
+                    record[team_name_column] = team_name.clone();
+                }
+            }
+            table.write(&config.pro_file_path)?;
+        }
+        Storage::Sqlite => db::update_pro(
+            &mut db::open(config)?,
+            (&pro.team.short_name, &pro.player_name),
+            (&team, name),
+            &team_name,
+        )?,
+    }
+
+    Ok(())
+}
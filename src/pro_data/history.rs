@@ -0,0 +1,331 @@
+use super::*;
+use crate::Storage;
+use chrono::Utc;
+use csv::{ReaderBuilder, WriterBuilder};
+use std::fs::{File, OpenOptions};
+use std::path::PathBuf;
+
+/// The ranked stats of one pro account at one point in time
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Snapshot {
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+    pub puuid: Puuid,
+    pub riot_id: String,
+    pub platform: String,
+    pub tier: String,
+    pub league_points: i32,
+    pub wins: i32,
+    pub losses: i32,
+}
+
+impl Snapshot {
+    pub fn games_played(&self) -> i32 {
+        self.wins + self.losses
+    }
+}
+
+fn history_file_path(config: &Config) -> PathBuf {
+    config.data_dir.join("ladder_history.csv")
+}
+
+/// Appends a snapshot of every ranked account of the given pros on the platforms whose ladders
+/// were just fetched from the API
+pub(super) fn record_snapshots(
+    config: &Config,
+    top_leagues: &TopLeagues,
+    pros: &[Arc<Pro>],
+) -> Result<(), Box<dyn Error>> {
+    let timestamp = Utc::now().timestamp();
+    let mut snapshots = Vec::new();
+
+    for account in pros.iter().flat_map(|pro| pro.accounts.iter()) {
+        if !top_leagues.refreshed.contains(&account.platform) {
+            continue;
+        }
+        let (puuid, summoner_id) = match (&account.puuid, &account.summoner_id) {
+            (Some(puuid), Some(summoner_id)) => (puuid, summoner_id),
+            _ => continue,
+        };
+        let rank = match top_leagues.get_rank(summoner_id) {
+            Some(r) => r,
+            None => continue,
+        };
+
+        snapshots.push(Snapshot {
+            timestamp,
+            puuid: puuid.clone(),
+            riot_id: account.riot_id.to_string(),
+            platform: account.platform.to_string(),
+            tier: rank.tier.to_string(),
+            league_points: rank.ranked_data.league_points,
+            wins: rank.ranked_data.wins,
+            losses: rank.ranked_data.losses,
+        });
+    }
+
+    if snapshots.is_empty() {
+        return Ok(());
+    }
+    if config.storage == Storage::Sqlite {
+        return db::append_snapshots(&mut db::open(config)?, &snapshots);
+    }
+
+    let path = history_file_path(config);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let is_new = std::fs::metadata(&path).map_or(true, |m| m.len() == 0);
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let mut writer = WriterBuilder::new().has_headers(is_new).from_writer(file);
+    for snapshot in snapshots {
+        writer.serialize(snapshot)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// How a pro's best account moved on the ladder since a reference snapshot
+#[derive(Debug, Clone)]
+pub struct LadderChange {
+    pub league_points: i32,
+    pub wins: i32,
+    pub losses: i32,
+    /// Position on the pro leaderboard among the entries that have a reference snapshot,
+    /// starting at 0
+    pub position: usize,
+    /// Position among the same entries at the time of the reference snapshots, starting at 0
+    pub previous_position: usize,
+}
+
+impl LadderChange {
+    pub fn games_played(&self) -> i32 {
+        self.wins + self.losses
+    }
+}
+
+/// Gets the snapshot of an account to compare against for changes since `since`
+/// # Returns
+/// The last snapshot taken at or before `since`, or the first one after it if there is none
+pub fn reference_snapshot<'a>(
+    snapshots: &'a [Snapshot],
+    puuid: &str,
+    since: i64,
+) -> Option<&'a Snapshot> {
+    let account_snapshots = || snapshots.iter().filter(|s| s.puuid == puuid);
+
+    account_snapshots()
+        .filter(|s| s.timestamp <= since)
+        .max_by_key(|s| s.timestamp)
+        .or_else(|| {
+            account_snapshots()
+                .filter(|s| s.timestamp > since)
+                .min_by_key(|s| s.timestamp)
+        })
+}
+
+/// Computes the ladder changes since `since` of every entry of a pro leaderboard
+/// # Returns
+/// One change per leaderboard entry, `None` for entries without a reference snapshot
+pub fn changes_since(
+    leaderboard: &[(Arc<Pro>, RankedStats)],
+    snapshots: &[Snapshot],
+    since: i64,
+) -> Vec<Option<LadderChange>> {
+    let references: Vec<Option<&Snapshot>> = leaderboard
+        .iter()
+        .map(|(pro, rank)| {
+            let puuid = pro
+                .account(&rank.ranked_data.summoner_id)
+                .and_then(|a| a.puuid.as_ref())?;
+            reference_snapshot(snapshots, puuid, since)
+        })
+        .collect();
+
+    /* Both positions are counted among the entries that have a reference snapshot, so that pros
+     * without history don't shift one position but not the other. The leaderboard is sorted
+     * already, the previous one is ordered by the LP of the reference snapshots */
+    let current_order: Vec<usize> = (0..leaderboard.len())
+        .filter(|&i| references[i].is_some())
+        .collect();
+    let mut previous_order = current_order.clone();
+    previous_order.sort_by_key(|&i| {
+        let reference = references[i].expect("entry has a reference snapshot");
+        std::cmp::Reverse((reference.league_points, reference.wins))
+    });
+    let position_of = |order: &[usize], i| {
+        order
+            .iter()
+            .position(|&j| j == i)
+            .expect("entry is in the order")
+    };
+
+    references
+        .iter()
+        .zip(leaderboard)
+        .enumerate()
+        .map(|(i, (reference, (_, rank)))| {
+            let reference = (*reference)?;
+            Some(LadderChange {
+                league_points: rank.ranked_data.league_points - reference.league_points,
+                wins: rank.ranked_data.wins - reference.wins,
+                losses: rank.ranked_data.losses - reference.losses,
+                position: position_of(&current_order, i),
+                previous_position: position_of(&previous_order, i),
+            })
+        })
+        .collect()
+}
+
+/// Loads all snapshots of the given pro's accounts, oldest first
+pub fn load_snapshots(config: &Config, pro: &Pro) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+    let mut result = load_all_snapshots(config)?;
+    result.retain(|s| pro.puuids().any(|puuid| *puuid == s.puuid));
+    Ok(result)
+}
+
+/// Loads the snapshots of all accounts, oldest first
+pub fn load_all_snapshots(config: &Config) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+    if config.storage == Storage::Sqlite {
+        return db::load_snapshots(&db::open(config)?);
+    }
+
+    let path = history_file_path(config);
+    let file = match File::open(&path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Box::new(e)),
+    };
+    let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
+
+    let mut result = Vec::new();
+    for record in reader.deserialize() {
+        let snapshot: Snapshot = match record {
+            Ok(s) => s,
+            Err(e) => {
+                ui::status(format!("Error reading ladder history {e}, skipping line"));
+                continue;
+            }
+        };
+        result.push(snapshot);
+    }
+
+    result.sort_by_key(|s| s.timestamp);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pro_data::test_util::ranked_pro;
+
+    const SINCE: i64 = 1_000;
+
+    fn snapshot(
+        name: &str,
+        timestamp: i64,
+        league_points: i32,
+        wins: i32,
+        losses: i32,
+    ) -> Snapshot {
+        Snapshot {
+            timestamp,
+            puuid: format!("puuid-{name}"),
+            riot_id: format!("{name}#EUW"),
+            platform: "EUW1".to_string(),
+            tier: "CHALLENGER".to_string(),
+            league_points,
+            wins,
+            losses,
+        }
+    }
+
+    #[test]
+    fn reference_snapshot_is_last_one_before_since() {
+        let snapshots = [
+            snapshot("Nova", SINCE - 200, 100, 10, 10),
+            snapshot("Nova", SINCE, 200, 20, 10),
+            snapshot("Tidal", SINCE - 50, 300, 30, 10),
+            snapshot("Nova", SINCE + 100, 300, 30, 10),
+        ];
+
+        let reference = reference_snapshot(&snapshots, "puuid-Nova", SINCE);
+        assert_eq!(reference.map(|s| s.league_points), Some(200));
+        let reference = reference_snapshot(&snapshots, "puuid-Nova", SINCE - 1);
+        assert_eq!(reference.map(|s| s.league_points), Some(100));
+    }
+
+    #[test]
+    fn reference_snapshot_falls_back_to_first_one_after_since() {
+        let snapshots = [
+            snapshot("Nova", SINCE + 200, 300, 30, 10),
+            snapshot("Nova", SINCE + 100, 200, 20, 10),
+            snapshot("Tidal", SINCE - 50, 100, 10, 10),
+        ];
+
+        let reference = reference_snapshot(&snapshots, "puuid-Nova", SINCE);
+        assert_eq!(reference.map(|s| s.league_points), Some(200));
+        assert!(reference_snapshot(&snapshots, "puuid-Ember", SINCE).is_none());
+    }
+
+    #[test]
+    fn changes_since_counts_positions_among_pros_with_history() {
+        let leaderboard = [
+            ranked_pro("Nova", 1000, 60, 40),
+            ranked_pro("Ember", 950, 50, 30),
+            ranked_pro("Tidal", 900, 55, 45),
+            ranked_pro("Quill", 800, 40, 40),
+        ];
+        let snapshots = [
+            snapshot("Nova", SINCE - 10, 500, 40, 30),
+            snapshot("Tidal", SINCE - 10, 700, 50, 40),
+            /* Quill has no snapshot before `since`, so the first one after it is used */
+            snapshot("Quill", SINCE + 10, 750, 38, 39),
+        ];
+
+        let changes = changes_since(&leaderboard, &snapshots, SINCE);
+        let changes: Vec<_> = changes
+            .iter()
+            .map(|change| {
+                change.as_ref().map(|c| {
+                    (
+                        c.league_points,
+                        c.wins,
+                        c.losses,
+                        c.position,
+                        c.previous_position,
+                    )
+                })
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                Some((500, 20, 10, 0, 2)),
+                None,
+                Some((200, 5, 5, 1, 1)),
+                Some((50, 2, 1, 2, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn changes_since_breaks_previous_ties_by_wins() {
+        let leaderboard = [
+            ranked_pro("Nova", 1000, 60, 40),
+            ranked_pro("Tidal", 900, 55, 45),
+        ];
+        let snapshots = [
+            snapshot("Nova", SINCE - 10, 700, 40, 30),
+            snapshot("Tidal", SINCE - 10, 700, 50, 40),
+        ];
+
+        let changes = changes_since(&leaderboard, &snapshots, SINCE);
+        let positions: Vec<Option<(usize, usize)>> = changes
+            .iter()
+            .map(|change| change.as_ref().map(|c| (c.position, c.previous_position)))
+            .collect();
+        assert_eq!(positions, [Some((0, 1)), Some((1, 0))]);
+    }
+}
@@ -1,16 +1,28 @@
 use super::*;
-use crate::api::RIOT_API;
+use crate::api::{self, RIOT_API};
+use crate::Storage;
 use csv::{ReaderBuilder, StringRecord, WriterBuilder};
-use std::io::{Error as IoError, ErrorKind};
+use riven::consts::RegionalRoute;
+use riven::reqwest::StatusCode;
+use std::io::Error as IoError;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::{error::Error, fs::File};
 
 #[derive(serde::Deserialize, serde::Serialize)]
-struct Row {
-    pro_name: String,
-    short_team: String,
-    long_team: String,
-    summoner_name: String,
-    summoner_id: String,
+pub(super) struct Row {
+    pub(super) pro_name: String,
+    pub(super) short_team: String,
+    pub(super) long_team: String,
+    /// Riot ID of the form `gameName#tagLine`. Older files call this column `summoner_name`.
+    #[serde(alias = "summoner_name")]
+    pub(super) riot_id: String,
+    /// Platform of the account, e.g. `EUW1` or `KR`. Empty means the default region.
+    #[serde(default)]
+    pub(super) region: String,
+    #[serde(default)]
+    pub(super) puuid: String,
+    pub(super) summoner_id: String,
 }
 
 lazy_static::lazy_static! {
@@ -18,108 +30,434 @@ lazy_static::lazy_static! {
         "pro_name",
         "short_team",
         "long_team",
-        "summoner_name",
+        "riot_id",
+        "region",
+        "puuid",
         "summoner_id",
     ]);
 }
 
-pub(super) async fn load_pros(config: &Config) -> Result<HashMap<String, Rc<Pro>>, Box<dyn Error>> {
-    let file = File::open(&config.pro_file_path)?;
-    let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
+/// Gets the platform of a row, falling back to the configured region if the row has none
+pub(super) fn row_platform(row: &Row, config: &Config) -> Result<PlatformRoute, String> {
+    match row.region.is_empty() {
+        true => Ok(config.region),
+        false => parse_platform(&row.region),
+    }
+}
 
-    let mut pros = HashMap::new();
+/// Parses the Riot ID of a row, telling how to fix rows from before Riot IDs that only have a
+/// summoner name
+pub(super) fn row_riot_id(row: &Row) -> Result<RiotId, String> {
+    if !row.riot_id.is_empty() && !row.riot_id.contains('#') {
+        return Err(format!(
+            "\"{}\" is a summoner name, which Riot no longer supports. Rewrite it as the \
+             account's Riot ID of the form Name#TAG, e.g. \"{}#EUW\"",
+            row.riot_id, row.riot_id
+        ));
+    }
+    row.riot_id.parse()
+}
 
-    for record in reader.records() {
-        let row: Row = match record {
-            Ok(r) => match r.deserialize(Some(&CSV_HEADER)) {
-                Ok(r) => r,
-                Err(_) => {
-                    dbg!("Error reading record");
-                    continue;
-                }
-            },
-            Err(e) => {
-                eprintln!("Error CSV record {e}, skipping line");
-                continue;
-            }
-        };
+/// Reads every row of a pros CSV file, skipping rows that can't be parsed
+pub(super) fn read_csv_rows(path: &str) -> Result<Vec<Row>, Box<dyn Error>> {
+    let mut rows = Vec::new();
+    for (line, row) in read_csv_lines(path)? {
+        match row {
+            Ok(r) => rows.push(r),
+            Err(e) => ui::status(format!(
+                "Error on line {line} of {path}: {e}, skipping line"
+            )),
+        }
+    }
 
-        let team = Team::new(row.short_team, row.long_team);
-        let pro = Pro::new(
-            row.pro_name,
-            team,
-            row.summoner_name,
-            row.summoner_id.clone(),
-        );
+    Ok(rows)
+}
+
+/// The line number of a CSV record and its row, or why it couldn't be parsed
+pub(super) type CsvLine = (u64, Result<Row, String>);
+
+/// Reads every row of a pros CSV file together with its line number
+pub(super) fn read_csv_lines(path: &str) -> Result<Vec<CsvLine>, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
+    let headers = reader.headers()?.clone();
 
-        pros.insert(row.summoner_id, Rc::new(pro));
+    let mut rows = Vec::new();
+    let mut record = StringRecord::new();
+    loop {
+        /* The position is that of the record about to be read */
+        let line = reader.position().line();
+        let row = match reader.read_record(&mut record) {
+            Ok(false) => break,
+            Ok(true) => record
+                .deserialize::<Row>(Some(&headers))
+                .map_err(|e| match e.kind() {
+                    csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+                    _ => e.to_string(),
+                }),
+            Err(e) if matches!(e.kind(), csv::ErrorKind::Io(_)) => return Err(Box::new(e)),
+            Err(e) => Err(e.to_string()),
+        };
+        rows.push((line, row));
     }
 
-    Ok(pros)
+    Ok(rows)
 }
 
-pub async fn sync_summoner_ids(config: &Config) -> Result<(), Box<dyn Error>> {
-    let old_file = File::open(&config.pro_file_path)?;
-    let mut reader = ReaderBuilder::new().has_headers(true).from_reader(old_file);
+/// Loads all pros from the CSV file or database. Each row is one account, rows with the same team
+/// and pro name are accounts of the same pro.
+pub async fn load_pros(config: &Config) -> Result<Vec<Arc<Pro>>, Box<dyn Error>> {
+    let rows = match config.storage {
+        Storage::Csv => read_csv_rows(&config.pro_file_path)?,
+        Storage::Sqlite => {
+            let connection = db::open(config)?;
+            db::load_rows(&connection)?
+                .into_iter()
+                .map(|(_, row)| row)
+                .collect()
+        }
+    };
+
+    pros_from_rows(rows, config)
+}
 
-    const NEW_FILE_NAME: &str = "/home/isak102/.cache/lolmsi043905-923j39";
-    let new_file = File::create(NEW_FILE_NAME)?;
-    let mut writer = WriterBuilder::new().has_headers(true).from_writer(new_file);
+/// Groups rows into pros
+/// # Returns
+/// An error naming every row that has a summoner name or a malformed Riot ID instead of a Riot ID,
+/// or an unknown region
+fn pros_from_rows(rows: Vec<Row>, config: &Config) -> Result<Vec<Arc<Pro>>, Box<dyn Error>> {
+    let mut pros: Vec<Pro> = Vec::new();
+    let mut pro_indices: HashMap<(String, String), usize> = HashMap::new();
+    let mut row_errors = Vec::new();
 
-    for record in reader.records() {
-        let row: Row = match record {
-            Ok(r) => match r.deserialize(Some(&CSV_HEADER)) {
-                Ok(r) => r,
-                Err(_) => {
-                    dbg!("Error reading record");
-                    continue;
+    for row in rows {
+        let (riot_id, platform) = match (row_riot_id(&row), row_platform(&row, config)) {
+            (Ok(riot_id), Ok(platform)) => (riot_id, platform),
+            (riot_id, platform) => {
+                for e in [riot_id.err(), platform.err()].into_iter().flatten() {
+                    row_errors.push(format!("{} {}: {e}", row.short_team, row.pro_name));
                 }
-            },
-            Err(e) => {
-                eprintln!("Error CSV record {e}, skipping line");
                 continue;
             }
         };
+        let account = Account::new(riot_id, platform, row.puuid, row.summoner_id);
+        let key = (row.short_team.to_uppercase(), row.pro_name.clone());
+        let index = *pro_indices.entry(key).or_insert_with(|| {
+            let team = Team::new(row.short_team, row.long_team);
+            pros.push(Pro::new(row.pro_name, team));
+            pros.len() - 1
+        });
 
-        let summoner_id = if row.summoner_id.is_empty() {
-            let s = get_summoner_id(&row.summoner_name).await?;
-            eprintln!("Found summoner id for {}: {}", row.pro_name, s);
-            s
-        } else {
-            row.summoner_id
-        };
+        pros[index].add_account(account);
+    }
+
+    if !row_errors.is_empty() {
+        return Err(Box::new(IoError::other(format!(
+            "the pro file has rows that can't be loaded, run validate for their lines:\n{}",
+            row_errors.join("\n")
+        ))));
+    }
+    Ok(pros.into_iter().map(Arc::new).collect())
+}
 
-        let new_row = Row {
-            pro_name: row.pro_name,
-            short_team: row.short_team,
-            long_team: row.long_team,
-            summoner_name: row.summoner_name,
-            summoner_id,
+/// A pros CSV file read as raw fields, so that columns this program doesn't know about survive
+/// edits. The columns are those of the file in their order, followed by any columns of
+/// `CSV_HEADER` that the file doesn't have.
+pub(super) struct CsvTable {
+    pub(super) headers: Vec<String>,
+    pub(super) records: Vec<Vec<String>>,
+}
+
+impl CsvTable {
+    pub(super) fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut reader = ReaderBuilder::new().has_headers(true).from_path(path)?;
+        let mut table = Self {
+            headers: reader.headers()?.iter().map(String::from).collect(),
+            records: Vec::new(),
         };
+        for header in CSV_HEADER.iter() {
+            if table.find_column(header).is_none() {
+                table.headers.push(header.to_string());
+            }
+        }
+
+        for record in reader.records() {
+            let mut record: Vec<String> = record?.iter().map(String::from).collect();
+            record.resize(table.headers.len(), String::new());
+            table.records.push(record);
+        }
 
-        writer.serialize(new_row)?;
+        Ok(table)
     }
 
-    std::fs::rename(NEW_FILE_NAME, &config.pro_file_path)
-        .expect("Updating data file failed while copying");
+    fn find_column(&self, name: &str) -> Option<usize> {
+        /* Older files call the riot_id column summoner_name */
+        self.headers
+            .iter()
+            .position(|h| h == name || (name == "riot_id" && h == "summoner_name"))
+    }
+
+    pub(super) fn column(&self, name: &str) -> usize {
+        self.find_column(name)
+            .expect("all columns of CSV_HEADER exist")
+    }
+
+    /// Parses a record of this table
+    pub(super) fn row(&self, record: &[String]) -> Row {
+        let field = |column: &str| record[self.column(column)].clone();
+        Row {
+            pro_name: field("pro_name"),
+            short_team: field("short_team"),
+            long_team: field("long_team"),
+            riot_id: field("riot_id"),
+            region: field("region"),
+            puuid: field("puuid"),
+            summoner_id: field("summoner_id"),
+        }
+    }
 
+    pub(super) fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        write_csv_atomically(path, &self.headers, &self.records)
+    }
+}
+
+/// Path of the temporary file that an atomic write of `path` goes through. It is unique to this
+/// process, so that concurrent runs don't write to the same temporary file
+fn temp_path(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let file_name = path.file_name().ok_or("path has no file name")?;
+    Ok(path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    )))
+}
+
+/// Writes a file by writing a temporary file next to it and renaming it over the file, so that an
+/// interrupted write never leaves a truncated file behind
+pub(super) fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+    let temp_path = temp_path(path)?;
+    let mut file = File::create(&temp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    std::fs::rename(&temp_path, path)?;
     Ok(())
 }
 
-async fn get_summoner_id(summoner_name: &SummonerName) -> Result<SummonerID, Box<dyn Error>> {
-    let summoner = match RIOT_API
-        .summoner_v4()
-        .get_by_summoner_name(PlatformRoute::EUW1, summoner_name)
-        .await?
+/// Writes a CSV file atomically, see `write_atomically`
+pub(super) fn write_csv_atomically<R: AsRef<[String]>>(
+    path: &str,
+    headers: &[String],
+    records: &[R],
+) -> Result<(), Box<dyn Error>> {
+    let path = Path::new(path);
+    let temp_path = temp_path(path)?;
+
+    let mut writer = WriterBuilder::new().from_path(&temp_path)?;
+    writer.write_record(headers)?;
+    for record in records {
+        writer.write_record(record.as_ref())?;
+    }
+    writer.into_inner()?.sync_all()?;
+
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Resolves a Riot ID to a PUUID through account-v1, using the regional route of the platform
+pub(super) async fn get_puuid(
+    platform: PlatformRoute,
+    riot_id: &RiotId,
+) -> Result<Puuid, Box<dyn Error>> {
+    let region: RegionalRoute = platform.to_regional();
+    let account = match api::request(
+        region.into(),
+        &format!(
+            "/riot/account/v1/accounts/by-riot-id/{}/{}",
+            riot_id.game_name, riot_id.tag_line
+        ),
+        RIOT_API
+            .account_v1()
+            .get_by_riot_id(region, &riot_id.game_name, &riot_id.tag_line),
+    )
+    .await?
     {
-        Some(summoner) => summoner,
+        Some(account) => account,
         None => {
-            return Err(Box::new(IoError::new(
-                ErrorKind::Other,
-                format!("Could not find summoner {}", summoner_name),
-            )));
+            return Err(Box::new(IoError::other(format!(
+                "Could not find account {}",
+                riot_id
+            ))));
         }
     };
 
+    Ok(account.puuid)
+}
+
+/// Gets the current Riot ID of a PUUID through account-v1
+/// # Returns
+/// `None` if no account has the PUUID anymore
+pub(super) async fn get_riot_id(
+    platform: PlatformRoute,
+    puuid: &Puuid,
+) -> Result<Option<RiotId>, Box<dyn Error>> {
+    let region: RegionalRoute = platform.to_regional();
+    let account = match api::request(
+        region.into(),
+        &format!("/riot/account/v1/accounts/by-puuid/{puuid}"),
+        RIOT_API.account_v1().get_by_puuid(region, puuid),
+    )
+    .await
+    {
+        Ok(account) => account,
+        Err(e) if e.status_code() == Some(StatusCode::NOT_FOUND) => return Ok(None),
+        Err(e) => return Err(Box::new(e)),
+    };
+
+    match (account.game_name, account.tag_line) {
+        (Some(game_name), Some(tag_line)) => Ok(Some(RiotId {
+            game_name,
+            tag_line,
+        })),
+        _ => Err(format!("Account {puuid} has no Riot ID").into()),
+    }
+}
+
+pub(super) async fn get_summoner_id(
+    platform: PlatformRoute,
+    puuid: &Puuid,
+) -> Result<SummonerID, Box<dyn Error>> {
+    let summoner = api::request(
+        platform.into(),
+        &format!("/lol/summoner/v4/summoners/by-puuid/{puuid}"),
+        RIOT_API.summoner_v4().get_by_puuid(platform, puuid),
+    )
+    .await?;
+
     Ok(summoner.id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn row(riot_id: &str, region: &str) -> Row {
+        Row {
+            pro_name: "Faker".to_string(),
+            short_team: "T1".to_string(),
+            long_team: "T1".to_string(),
+            riot_id: riot_id.to_string(),
+            region: region.to_string(),
+            puuid: String::new(),
+            summoner_id: String::new(),
+        }
+    }
+
+    #[test]
+    fn row_riot_id_parses_riot_ids() {
+        let riot_id = row_riot_id(&row("Hide on bush#KR1", "KR")).expect("should parse");
+        assert_eq!(riot_id.to_string(), "Hide on bush#KR1");
+    }
+
+    #[test]
+    fn row_riot_id_rejects_summoner_names() {
+        let e = row_riot_id(&row("Hide on bush", "KR")).expect_err("should be rejected");
+        assert!(e.contains("Name#TAG"), "{e}");
+        assert!(e.contains("\"Hide on bush#EUW\""), "{e}");
+    }
+
+    #[test]
+    fn row_riot_id_rejects_malformed_riot_ids() {
+        assert!(row_riot_id(&row("#KR1", "KR")).is_err());
+        assert!(row_riot_id(&row("", "KR")).is_err());
+    }
+
+    #[test]
+    fn csv_table_keeps_columns() {
+        let dir = test_util::temp_dir("csv_table_keeps_columns");
+        let path = dir.join("pros.csv").to_string_lossy().to_string();
+        std::fs::write(
+            &path,
+            "notes,summoner_name,pro_name,short_team,long_team,summoner_id\n\
+             \"mid, main\",Hide on bush#KR1,Faker,T1,T1,sid-faker\n\
+             ,Caps#EUW,Caps,G2,G2 Esports,\n",
+        )
+        .expect("should write pros");
+
+        let mut table = CsvTable::read(&path).expect("table should be read");
+        assert_eq!(
+            table.headers,
+            [
+                "notes",
+                "summoner_name",
+                "pro_name",
+                "short_team",
+                "long_team",
+                "summoner_id",
+                "region",
+                "puuid"
+            ]
+        );
+        let row = table.row(&table.records[0]);
+        assert_eq!(row.riot_id, "Hide on bush#KR1");
+        assert_eq!(row.summoner_id, "sid-faker");
+
+        let puuid = table.column("puuid");
+        table.records[1][puuid] = "puuid-caps".to_string();
+        table.write(&path).expect("table should be written");
+        assert_eq!(
+            std::fs::read_to_string(&path).expect("should read pros"),
+            "notes,summoner_name,pro_name,short_team,long_team,summoner_id,region,puuid\n\
+             \"mid, main\",Hide on bush#KR1,Faker,T1,T1,sid-faker,,\n\
+             ,Caps#EUW,Caps,G2,G2 Esports,,,puuid-caps\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn load_pros_reads_legacy_header() {
+        let dir = test_util::temp_dir("load_pros_reads_legacy_header");
+        std::fs::write(
+            dir.join("pros.csv"),
+            "pro_name,short_team,long_team,summoner_name,region,puuid,summoner_id\n\
+             Faker,T1,T1,Hide on bush#KR1,KR,puuid-faker,sid-faker\n\
+             Faker,T1,T1,Faker2#KR1,KR,,\n",
+        )
+        .expect("should write pros");
+        let config = test_util::config(&dir, &[]);
+
+        let pros = load_pros(&config).await.expect("pros should load");
+        assert_eq!(pros.len(), 1);
+        let riot_ids: Vec<String> = pros[0]
+            .accounts()
+            .iter()
+            .map(|a| a.riot_id().to_string())
+            .collect();
+        assert_eq!(riot_ids, ["Hide on bush#KR1", "Faker2#KR1"]);
+    }
+
+    #[tokio::test]
+    async fn load_pros_rejects_summoner_names_and_bad_regions() {
+        let dir = test_util::temp_dir("load_pros_rejects_summoner_names_and_bad_regions");
+        std::fs::write(
+            dir.join("pros.csv"),
+            "pro_name,short_team,long_team,summoner_name,region,puuid,summoner_id\n\
+             Faker,T1,T1,Hide on bush,KR,,\n\
+             Caps,G2,G2 Esports,Caps#EUW,Atlantis,,\n\
+             Ruler,GEN,Gen.G,Ruler#KR1,KR,,\n",
+        )
+        .expect("should write pros");
+        let config = test_util::config(&dir, &[]);
+
+        let e = load_pros(&config)
+            .await
+            .expect_err("load should fail")
+            .to_string();
+        assert!(
+            e.contains("T1 Faker: \"Hide on bush\" is a summoner name"),
+            "{e}"
+        );
+        assert!(e.contains("G2 Caps: "), "{e}");
+        assert!(!e.contains("Ruler"), "{e}");
+    }
+}
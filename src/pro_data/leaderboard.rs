@@ -0,0 +1,342 @@
+//! Filtering and sorting of the pro leaderboard, and the team leaderboard aggregated from it
+
+use std::cmp::Ordering;
+
+use super::history::LadderChange;
+use super::*;
+
+/// What the leaderboard is sorted by, highest first. Ties are broken by wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum SortBy {
+    #[default]
+    Lp,
+    Winrate,
+    Games,
+    /// LP gained since `--since`
+    LpGained,
+}
+
+/// Which pros are on the leaderboard, decided by the ranked stats of their best account
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// Short or full name of the team, compared case-insensitively
+    pub team: Option<String>,
+    pub tier: Option<Tier>,
+    pub platform: Option<PlatformRoute>,
+    pub min_games: Option<u32>,
+    /// Minimum winrate in percent
+    pub min_winrate: Option<f32>,
+}
+
+impl Filter {
+    fn matches(&self, pro: &Pro, rank: &RankedStats) -> bool {
+        let team_matches = |team: &String| {
+            pro.team().eq_ignore_ascii_case(team) || pro.team_name().eq_ignore_ascii_case(team)
+        };
+
+        self.team.as_ref().is_none_or(team_matches)
+            && self.tier.is_none_or(|tier| rank.tier == tier)
+            && self
+                .platform
+                .is_none_or(|platform| rank.platform == platform)
+            && self.min_games.is_none_or(|min| rank.games_played() >= min)
+            && self
+                .min_winrate
+                .is_none_or(|min| rank.winrate().is_some_and(|winrate| winrate >= min))
+    }
+}
+
+/// A pro on the leaderboard
+#[derive(Debug)]
+pub struct Entry {
+    pub pro: Arc<Pro>,
+    /// Ranked stats of the pro's best account
+    pub rank: RankedStats,
+    /// Position on the unfiltered leaderboard sorted by LP, starting at 0
+    pub position: usize,
+    pub change: Option<LadderChange>,
+}
+
+/// Filters and sorts a leaderboard
+/// # Parameters
+/// `leaderboard` - every ranked pro sorted by LP, see `ProData::pro_leaderboard`
+/// `changes` - ladder changes of each leaderboard entry, see `history::changes_since`. Needed
+/// to sort by `SortBy::LpGained`, entries without a change are sorted last
+/// `top` - how many entries to keep after sorting, all of them if `None`
+pub fn entries(
+    leaderboard: Vec<(Arc<Pro>, RankedStats)>,
+    changes: Option<Vec<Option<LadderChange>>>,
+    filter: &Filter,
+    sort_by: SortBy,
+    top: Option<usize>,
+) -> Vec<Entry> {
+    let mut changes = changes.unwrap_or_default().into_iter();
+    let mut result: Vec<Entry> = leaderboard
+        .into_iter()
+        .enumerate()
+        .map(|(position, (pro, rank))| Entry {
+            pro,
+            rank,
+            position,
+            change: changes.next().flatten(),
+        })
+        .filter(|entry| filter.matches(&entry.pro, &entry.rank))
+        .collect();
+
+    /* The sort is stable, so entries that are tied on wins too stay in LP order */
+    result.sort_by(|a, b| compare(sort_by, b, a));
+    if let Some(top) = top {
+        result.truncate(top);
+    }
+    result
+}
+
+fn compare(sort_by: SortBy, a: &Entry, b: &Entry) -> Ordering {
+    let ordering = match sort_by {
+        SortBy::Lp => a
+            .rank
+            .ranked_data
+            .league_points
+            .cmp(&b.rank.ranked_data.league_points),
+        /* Pros without games have no winrate and are sorted last */
+        SortBy::Winrate => match (a.rank.winrate(), b.rank.winrate()) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        },
+        SortBy::Games => a.rank.games_played().cmp(&b.rank.games_played()),
+        SortBy::LpGained => {
+            let gained = |entry: &Entry| entry.change.as_ref().map(|c| c.league_points);
+            gained(a).cmp(&gained(b))
+        }
+    };
+
+    ordering.then(a.rank.ranked_data.wins.cmp(&b.rank.ranked_data.wins))
+}
+
+/// The pros of a team, see `teams`
+#[derive(Debug)]
+pub struct TeamStats {
+    /// Short name of the team, e.g. `G2`
+    pub short_name: String,
+    /// Full name of the team, e.g. `G2 Esports`
+    pub full_name: String,
+    /// Number of pros of the team, ranked or not
+    pub pros: usize,
+    /// The pros of the team on the leaderboard with their best account, highest LP first
+    pub ranked: Vec<(Arc<Pro>, RankedStats)>,
+}
+
+impl TeamStats {
+    /// Number of pros in master or above
+    pub fn apex_players(&self) -> usize {
+        self.ranked.len()
+    }
+
+    pub fn total_lp(&self) -> i32 {
+        self.ranked
+            .iter()
+            .map(|(_, rank)| rank.ranked_data.league_points)
+            .sum()
+    }
+
+    pub fn average_lp(&self) -> i32 {
+        self.total_lp() / self.apex_players() as i32
+    }
+
+    pub fn wins(&self) -> i32 {
+        self.ranked
+            .iter()
+            .map(|(_, rank)| rank.ranked_data.wins)
+            .sum()
+    }
+
+    pub fn losses(&self) -> i32 {
+        self.ranked
+            .iter()
+            .map(|(_, rank)| rank.ranked_data.losses)
+            .sum()
+    }
+
+    pub fn games_played(&self) -> u32 {
+        (self.wins() + self.losses()) as u32
+    }
+
+    /// Winrate of all games of the ranked pros combined, in percent. `None` if they haven't
+    /// played any games
+    pub fn winrate(&self) -> Option<f32> {
+        match self.games_played() {
+            0 => None,
+            games => Some((self.wins() as f32 / games as f32) * 100_f32),
+        }
+    }
+
+    /// The pro with the most LP and their best account
+    pub fn best_player(&self) -> &(Arc<Pro>, RankedStats) {
+        &self.ranked[0]
+    }
+}
+
+/// Groups the pros on the leaderboard by team
+/// # Parameters
+/// `leaderboard` - every ranked pro sorted by LP, see `ProData::pro_leaderboard`
+/// # Returns
+/// Every team with a pro on the leaderboard, sorted by average LP and then by wins
+pub fn teams(pro_data: &ProData, leaderboard: Vec<(Arc<Pro>, RankedStats)>) -> Vec<TeamStats> {
+    let mut team_sizes: HashMap<&str, usize> = HashMap::new();
+    for pro in &pro_data.pros {
+        *team_sizes.entry(pro.team()).or_insert(0) += 1;
+    }
+
+    let mut result: Vec<TeamStats> = Vec::new();
+    for (pro, rank) in leaderboard {
+        match result.iter_mut().find(|t| t.short_name == pro.team()) {
+            Some(team) => team.ranked.push((pro, rank)),
+            None => result.push(TeamStats {
+                short_name: pro.team().to_string(),
+                full_name: pro.team_name().to_string(),
+                pros: team_sizes.get(pro.team()).copied().unwrap_or_default(),
+                ranked: vec![(pro, rank)],
+            }),
+        }
+    }
+
+    result.sort_by_key(|team| std::cmp::Reverse((team.average_lp(), team.wins())));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pro_data::test_util::{pro, rank, ranked_pro};
+
+    fn entry((pro, rank): (Arc<Pro>, RankedStats)) -> Entry {
+        Entry {
+            pro,
+            rank,
+            position: 0,
+            change: None,
+        }
+    }
+
+    fn names(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.pro.name()).collect()
+    }
+
+    #[test]
+    fn filter_matches_team_case_insensitively() {
+        let pro = pro("Caps", "G2");
+        let rank = rank(&pro, Tier::CHALLENGER, 1000, 60, 40);
+
+        for team in ["G2", "g2", "G2 Esports", "g2 esports"] {
+            let filter = Filter {
+                team: Some(team.to_string()),
+                ..Filter::default()
+            };
+            assert!(filter.matches(&pro, &rank), "{team}");
+        }
+        let filter = Filter {
+            team: Some("T1".to_string()),
+            ..Filter::default()
+        };
+        assert!(!filter.matches(&pro, &rank));
+    }
+
+    #[test]
+    fn filter_matches_tier_and_platform() {
+        let (pro, mut rank) = ranked_pro("Caps", 1000, 60, 40);
+        let by_tier = |tier| Filter {
+            tier: Some(tier),
+            ..Filter::default()
+        };
+        let by_platform = |platform| Filter {
+            platform: Some(platform),
+            ..Filter::default()
+        };
+
+        assert!(Filter::default().matches(&pro, &rank));
+        assert!(by_tier(Tier::CHALLENGER).matches(&pro, &rank));
+        assert!(!by_tier(Tier::GRANDMASTER).matches(&pro, &rank));
+        assert!(by_platform(PlatformRoute::EUW1).matches(&pro, &rank));
+        rank.platform = PlatformRoute::KR;
+        assert!(!by_platform(PlatformRoute::EUW1).matches(&pro, &rank));
+    }
+
+    #[test]
+    fn filter_matches_min_games_and_winrate() {
+        let (pro, rank) = ranked_pro("Caps", 1000, 60, 40);
+        let by_games = |min| Filter {
+            min_games: Some(min),
+            ..Filter::default()
+        };
+        let by_winrate = |min| Filter {
+            min_winrate: Some(min),
+            ..Filter::default()
+        };
+
+        assert!(by_games(100).matches(&pro, &rank));
+        assert!(!by_games(101).matches(&pro, &rank));
+        assert!(by_winrate(60.0).matches(&pro, &rank));
+        assert!(!by_winrate(60.1).matches(&pro, &rank));
+
+        let (pro, rank) = ranked_pro("Hans", 1000, 0, 0);
+        assert_eq!(rank.winrate(), None);
+        assert!(!by_winrate(0.0).matches(&pro, &rank));
+    }
+
+    #[test]
+    fn compare_sorts_by_each_column() {
+        let change = |league_points| LadderChange {
+            league_points,
+            wins: 0,
+            losses: 0,
+            position: 0,
+            previous_position: 0,
+        };
+        let leaderboard = vec![
+            ranked_pro("Nova", 1000, 60, 60),
+            ranked_pro("Tidal", 900, 70, 30),
+            ranked_pro("Ember", 800, 20, 10),
+            ranked_pro("Quill", 700, 0, 0),
+        ];
+        let changes = vec![Some(change(50)), None, Some(change(100)), Some(change(-10))];
+        let sorted = |sort_by| {
+            let entries = entries(
+                leaderboard.clone(),
+                Some(changes.clone()),
+                &Filter::default(),
+                sort_by,
+                None,
+            );
+            names(&entries).join(" ")
+        };
+
+        assert_eq!(sorted(SortBy::Lp), "Nova Tidal Ember Quill");
+        assert_eq!(sorted(SortBy::Winrate), "Tidal Ember Nova Quill");
+        assert_eq!(sorted(SortBy::Games), "Nova Tidal Ember Quill");
+        assert_eq!(sorted(SortBy::LpGained), "Ember Nova Quill Tidal");
+    }
+
+    #[test]
+    fn compare_breaks_ties_by_wins() {
+        let a = entry(ranked_pro("Nova", 1000, 50, 50));
+        let b = entry(ranked_pro("Tidal", 1000, 60, 60));
+
+        for sort_by in [SortBy::Lp, SortBy::Winrate, SortBy::LpGained] {
+            assert_eq!(compare(sort_by, &a, &b), Ordering::Less, "{sort_by:?}");
+            assert_eq!(compare(sort_by, &b, &a), Ordering::Greater, "{sort_by:?}");
+        }
+        let c = entry(ranked_pro("Ember", 900, 70, 50));
+        assert_eq!(compare(SortBy::Games, &b, &c), Ordering::Less);
+
+        let no_games = entry(ranked_pro("Quill", 1000, 0, 0));
+        let one_loss = entry(ranked_pro("Hans", 1000, 0, 1));
+        assert_eq!(
+            compare(SortBy::Winrate, &no_games, &one_loss),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare(SortBy::Winrate, &no_games, &no_games),
+            Ordering::Equal
+        );
+    }
+}
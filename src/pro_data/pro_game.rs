@@ -5,7 +5,6 @@ use chrono::{DateTime, Local, TimeZone};
 use riven::consts::Team;
 use std::panic;
 use std::str;
-use strip_ansi_escapes;
 use yansi::Paint;
 
 #[derive(Debug, Clone)]
@@ -14,7 +13,7 @@ pub struct Player {
     pub current_game_participant: CurrentGameParticipant,
 }
 
-impl<'a> Player {
+impl Player {
     pub(super) fn new(
         summoner_id: &str,
         current_game_participant: CurrentGameParticipant,
@@ -33,10 +32,9 @@ impl<'a> Player {
     }
 
     pub fn get_lp(&self) -> Option<i32> {
-        match &self.ranked_stats {
-            Some(r) => Some(r.ranked_data.league_points),
-            None => None,
-        }
+        self.ranked_stats
+            .as_ref()
+            .map(|r| r.ranked_data.league_points)
     }
 }
 
@@ -44,13 +42,13 @@ impl<'a> Player {
 pub struct ProGame {
     pub(super) game_info: CurrentGameInfo,
     pub(super) players: Vec<Player>,
-    pub(super) pro_players: Vec<Rc<Pro>>,
+    pub(super) pro_players: Vec<Arc<Pro>>,
 }
 
 impl ProGame {
-    /// Get pro by summoner_id
+    /// Get pro by the summoner ID of any of their accounts
     /// # Parameters
-    /// `summoner_id` - Summoner ID of the pro
+    /// `summoner_id` - Summoner ID of one of the pro's accounts
     /// # Returns
     /// - `Some(p)` if pro was found
     /// - `None` if pro was not found
@@ -58,7 +56,7 @@ impl ProGame {
         let p = self
             .pro_players
             .iter()
-            .find(|pro| pro.summoner_id.as_deref() == Some(summoner_id));
+            .find(|pro| pro.has_account(summoner_id));
 
         match p {
             Some(p) => Some(p.as_ref()),
@@ -66,6 +64,11 @@ impl ProGame {
         }
     }
 
+    /// The pros playing in this game
+    pub fn pros(&self) -> &[Arc<Pro>] {
+        &self.pro_players
+    }
+
     /// Get the teams in the game
     /// # Returns
     /// A tuple of vectors with references to each player in the team. Both vectors will have the
@@ -80,7 +83,8 @@ impl ProGame {
         (blue, red)
     }
 
-    pub fn average_lp(&self) -> i32 {
+    /// Average LP of the ranked players in the game, `None` if none of them is ranked
+    pub fn average_lp(&self) -> Option<i32> {
         let mut total_lp = 0;
         let mut results = 0;
 
@@ -92,7 +96,36 @@ impl ProGame {
             results += 1;
         }
 
-        total_lp / results
+        match results {
+            0 => None,
+            _ => Some(total_lp / results),
+        }
+    }
+
+    /// The platform the game is played on
+    pub fn platform(&self) -> Option<PlatformRoute> {
+        parse_platform(&self.game_info.platform_id).ok()
+    }
+
+    pub fn game_id(&self) -> i64 {
+        self.game_info.game_id
+    }
+
+    /// Unix timestamp in milliseconds of when the game started, 0 if it hasn't yet
+    pub fn start_time(&self) -> i64 {
+        self.game_info.game_start_time
+    }
+
+    /// How long the game has been going on, based on its start time rather than the game length
+    /// reported by the spectator API, which lags behind
+    /// # Returns
+    /// `None` if the game hasn't started yet
+    pub fn game_time(&self) -> Option<std::time::Duration> {
+        if self.game_info.game_start_time <= 0 {
+            return None;
+        }
+        let elapsed_ms = chrono::Utc::now().timestamp_millis() - self.game_info.game_start_time;
+        Some(std::time::Duration::from_millis(elapsed_ms.max(0) as u64))
     }
 
     pub fn get_player(&self, summoner_id: &str) -> Option<&Player> {
@@ -104,20 +137,13 @@ impl ProGame {
 
 // FIXME: Move the functions in this file somewhere else
 
-// FIXME: game time is very inaccurate, use game_start_time to calculate instead
-fn game_length_to_string(game_length: i64) -> String {
-    let minutes = game_length / 60;
-    let seconds = game_length % 60;
-
-    format!("{:02}:{:02}", minutes, seconds)
-}
-
-fn ansicode_length(str: &str) -> usize {
+/// Display width of a string that may contain ANSI escape codes, which take up no columns
+fn ansi_display_width(str: &str) -> usize {
     let stripped_string = str::from_utf8(&strip_ansi_escapes::strip(str).unwrap())
         .unwrap()
         .to_string();
 
-    str.len() - stripped_string.len()
+    crate::ui::display_width(&stripped_string)
 }
 
 fn start_time_to_string(start_time: i64) -> String {
@@ -155,35 +181,27 @@ fn participant_to_string(participant: &CurrentGameParticipant, is_pro: (bool, &s
     result
 }
 
-fn banned_champions_to_string(banned_champions: &Vec<BannedChampion>) -> (String, String) {
+fn banned_champions_to_string(banned_champions: &[BannedChampion]) -> (String, String) {
     let mut blue_string = String::new();
     let mut red_string = String::new();
 
-    let _: Vec<&BannedChampion> = banned_champions
-        .iter()
-        .map(|champ| {
-            let push_champ_string = |s: &mut String| {
-                let banned_champ_str = match champ.champion_id.name() {
-                    Some(champ) => champ,
-                    None => "None",
-                };
+    for champ in banned_champions {
+        let push_champ_string = |s: &mut String| {
+            let banned_champ_str = champ.champion_id.name().unwrap_or("None");
 
-                s.push_str(format!("{banned_champ_str}, ").as_str());
-            };
+            s.push_str(format!("{banned_champ_str}, ").as_str());
+        };
 
-            match champ.team_id {
-                Team::BLUE => {
-                    push_champ_string(&mut blue_string);
-                }
-                Team::RED => {
-                    push_champ_string(&mut red_string);
-                }
-                _ => panic!("Champion was not banned by either BLUE or RED team"),
+        match champ.team_id {
+            Team::BLUE => {
+                push_champ_string(&mut blue_string);
             }
-
-            champ
-        })
-        .collect();
+            Team::RED => {
+                push_champ_string(&mut red_string);
+            }
+            _ => panic!("Champion was not banned by either BLUE or RED team"),
+        }
+    }
 
     let suffix = ", ";
     let remove_suffix = |s: &mut String| {
@@ -0,0 +1,255 @@
+use super::io::{CsvTable, Row};
+use super::*;
+use crate::Storage;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+
+/// What a sync changed, or would have changed in a dry run
+#[derive(Default, serde::Serialize)]
+pub struct SyncReport {
+    /// One line per changed account
+    pub changes: Vec<String>,
+    /// Every account that couldn't be synced
+    pub failures: Vec<SyncFailure>,
+    /// Accounts whose PUUID no longer belongs to an account, found when verifying
+    pub missing: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct SyncFailure {
+    pub riot_id: String,
+    pub error: String,
+}
+
+/// The current IDs of an account
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct ResolvedAccount {
+    riot_id: String,
+    puuid: Puuid,
+    summoner_id: SummonerID,
+    /// Whether existing IDs were checked against the API instead of kept as they were
+    verified: bool,
+}
+
+/// IDs resolved by a sync that hasn't written them to the pro file yet, so that an interrupted
+/// sync can continue where it stopped instead of resolving everything again
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+struct Progress {
+    pro_file_path: String,
+    /// Hash of the pro file when the sync started, see `pro_file_hash`
+    #[serde(default)]
+    pro_file_hash: u64,
+    /// Accounts keyed by `platform:riot_id`, with the Riot ID as stored in the pro file
+    resolved: HashMap<String, ResolvedAccount>,
+}
+
+fn progress_path(config: &Config) -> PathBuf {
+    config.cache_dir.join("sync_progress.json")
+}
+
+/// Hash of the contents of the pro file, so that progress isn't applied to a file that was edited
+/// since. It is only stable for one build, a new build at worst starts the sync over.
+fn pro_file_hash(config: &Config) -> Result<u64, Box<dyn Error>> {
+    let mut hasher = DefaultHasher::new();
+    std::fs::read(&config.pro_file_path)?.hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+fn load_progress(config: &Config, pro_file_hash: u64) -> Progress {
+    let progress = std::fs::read_to_string(progress_path(config))
+        .ok()
+        .and_then(|json| serde_json::from_str::<Progress>(&json).ok())
+        .filter(|p| p.pro_file_path == config.pro_file_path && p.pro_file_hash == pro_file_hash);
+
+    match progress {
+        Some(p) => {
+            ui::status(format!(
+                "Resuming interrupted sync with {} account(s) already resolved",
+                p.resolved.len()
+            ));
+            p
+        }
+        None => Progress {
+            pro_file_path: config.pro_file_path.clone(),
+            pro_file_hash,
+            ..Default::default()
+        },
+    }
+}
+
+fn save_progress(config: &Config, progress: &Progress) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(&config.cache_dir)?;
+    io::write_atomically(
+        &progress_path(config),
+        serde_json::to_string(progress)?.as_bytes(),
+    )
+}
+
+/// Fills in missing PUUIDs and summoner IDs by resolving each row's Riot ID. Accounts that can't
+/// be resolved are reported instead of stopping the sync.
+/// # Parameters
+/// `dry_run`: only report what would change, without writing anything
+/// `verify`: also check the IDs of accounts that have them, updating the Riot IDs of renamed
+/// accounts and reporting accounts that no longer exist
+pub async fn sync_summoner_ids(
+    config: &Config,
+    dry_run: bool,
+    verify: bool,
+) -> Result<SyncReport, Box<dyn Error>> {
+    /* Rows are identified by their index in the CSV file or their account ID in the database */
+    let (mut table, connection) = match config.storage {
+        Storage::Csv => (Some(CsvTable::read(&config.pro_file_path)?), None),
+        Storage::Sqlite => (None, Some(db::open(config)?)),
+    };
+    let rows: Vec<(i64, Row)> = match (&table, &connection) {
+        (Some(table), _) => table
+            .records
+            .iter()
+            .enumerate()
+            .map(|(i, record)| (i as i64, table.row(record)))
+            .collect(),
+        (None, Some(connection)) => db::load_rows(connection)?,
+        (None, None) => unreachable!("either a CSV table or a database is open"),
+    };
+
+    let mut progress = load_progress(config, pro_file_hash(config)?);
+    let mut report = SyncReport::default();
+
+    for (id, row) in rows {
+        if !verify && !row.puuid.is_empty() && !row.summoner_id.is_empty() {
+            continue;
+        }
+
+        let platform = match io::row_platform(&row, config) {
+            Ok(p) => p,
+            Err(e) => {
+                report.failures.push(SyncFailure {
+                    riot_id: row.riot_id,
+                    error: e,
+                });
+                continue;
+            }
+        };
+        let key = format!("{platform}:{}", row.riot_id);
+        let cached = progress
+            .resolved
+            .get(&key)
+            .filter(|account| account.verified || !verify);
+        let account = match cached {
+            Some(account) => account.clone(),
+            None => match resolve_account(&row, platform, verify).await {
+                Ok(Some(account)) => {
+                    progress.resolved.insert(key, account.clone());
+                    if !dry_run {
+                        save_progress(config, &progress)?;
+                    }
+                    ui::status(format!("Resolved {} of {}", row.riot_id, row.pro_name));
+                    account
+                }
+                Ok(None) => {
+                    ui::status(format!("{} no longer exists", row.riot_id));
+                    report.missing.push(format!(
+                        "{} {} {} ({})",
+                        row.short_team, row.pro_name, row.riot_id, row.puuid
+                    ));
+                    continue;
+                }
+                Err(e) => {
+                    ui::status(format!("Could not resolve {}: {e}", row.riot_id));
+                    report.failures.push(SyncFailure {
+                        riot_id: row.riot_id,
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+            },
+        };
+
+        let mut change = format!("{} {} {}:", row.short_team, row.pro_name, row.riot_id);
+        if row.riot_id != account.riot_id {
+            change += &format!(" renamed to {}", account.riot_id);
+        }
+        if row.puuid != account.puuid {
+            change += &format!(" PUUID {}", account.puuid);
+        }
+        if row.summoner_id != account.summoner_id {
+            change += &format!(" summoner ID {}", account.summoner_id);
+        }
+        if change.ends_with(':') {
+            continue;
+        }
+        report.changes.push(change);
+
+        match (&mut table, &connection) {
+            (Some(table), _) => {
+                let columns = [
+                    (table.column("riot_id"), account.riot_id),
+                    (table.column("puuid"), account.puuid),
+                    (table.column("summoner_id"), account.summoner_id),
+                ];
+                let record = &mut table.records[id as usize];
+                for (column, value) in columns {
+                    record[column] = value;
+                }
+            }
+            /* The database is updated right away so that it never needs resuming */
+            (None, Some(connection)) if !dry_run => db::update_account(
+                connection,
+                id,
+                &account.riot_id,
+                &account.puuid,
+                &account.summoner_id,
+            )?,
+            (None, _) => {}
+        }
+    }
+
+    if dry_run {
+        return Ok(report);
+    }
+    if let Some(table) = table {
+        if !report.changes.is_empty() {
+            table.write(&config.pro_file_path)?;
+        }
+    }
+    match std::fs::remove_file(progress_path(config)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(Box::new(e)),
+        _ => {}
+    }
+
+    Ok(report)
+}
+
+/// Resolves the PUUID and summoner ID of a row, keeping those that are already set unless
+/// verifying
+/// # Returns
+/// `None` if verifying and the PUUID no longer belongs to an account
+async fn resolve_account(
+    row: &Row,
+    platform: PlatformRoute,
+    verify: bool,
+) -> Result<Option<ResolvedAccount>, Box<dyn Error>> {
+    let puuid = match row.puuid.is_empty() {
+        true => io::get_puuid(platform, &row.riot_id.parse()?).await?,
+        false => row.puuid.clone(),
+    };
+    /* The PUUID never changes, so it is used to find the current Riot ID */
+    let riot_id = match verify && !row.puuid.is_empty() {
+        true => match io::get_riot_id(platform, &puuid).await? {
+            Some(riot_id) => riot_id.to_string(),
+            None => return Ok(None),
+        },
+        false => row.riot_id.clone(),
+    };
+    let summoner_id = match verify || row.summoner_id.is_empty() {
+        true => io::get_summoner_id(platform, &puuid).await?,
+        false => row.summoner_id.clone(),
+    };
+
+    Ok(Some(ResolvedAccount {
+        riot_id,
+        puuid,
+        summoner_id,
+        verified: verify,
+    }))
+}
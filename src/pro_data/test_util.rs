@@ -0,0 +1,62 @@
+//! Helpers shared by the unit tests of the pro data modules
+
+use super::*;
+
+/// A pro with one account, `puuid-<name>` and `sid-<name>` as its IDs
+pub fn pro(name: &str, team: &str) -> Arc<Pro> {
+    let mut pro = Pro::new(
+        name.to_string(),
+        Team {
+            short_name: team.to_string(),
+            full_name: format!("{team} Esports"),
+        },
+    );
+    pro.add_account(Account::new(
+        RiotId {
+            game_name: name.to_string(),
+            tag_line: "EUW".to_string(),
+        },
+        PlatformRoute::EUW1,
+        format!("puuid-{name}"),
+        format!("sid-{name}"),
+    ));
+    Arc::new(pro)
+}
+
+/// Ranked stats of the account of a pro made by `pro`
+pub fn rank(pro: &Pro, tier: Tier, league_points: i32, wins: i32, losses: i32) -> RankedStats {
+    let account = &pro.accounts()[0];
+    RankedStats {
+        tier,
+        ranked_data: LeagueItem {
+            fresh_blood: false,
+            wins,
+            summoner_name: account.riot_id().game_name.clone(),
+            mini_series: None,
+            inactive: false,
+            veteran: false,
+            hot_streak: false,
+            rank: riven::consts::Division::I,
+            league_points,
+            losses,
+            summoner_id: account
+                .summoner_id()
+                .expect("account should have a summoner ID")
+                .to_string(),
+        },
+        platform: account.platform(),
+        ladder_position: 1,
+    }
+}
+
+/// A leaderboard entry of a new pro, see `ProData::pro_leaderboard`
+pub fn ranked_pro(
+    name: &str,
+    league_points: i32,
+    wins: i32,
+    losses: i32,
+) -> (Arc<Pro>, RankedStats) {
+    let pro = pro(name, "T1");
+    let rank = rank(&pro, Tier::CHALLENGER, league_points, wins, losses);
+    (pro, rank)
+}
@@ -1,66 +1,195 @@
-use crate::api::RIOT_API;
+use crate::api::{self, RIOT_API};
 
-use super::{RankedStats, SummonerID};
+use super::{io, RankedStats, SummonerID};
+use chrono::Utc;
 use riven::{
-    consts::Tier,
+    consts::{PlatformRoute, Tier},
     models::league_v4::{LeagueItem, LeagueList},
-    RiotApiError,
 };
 use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
 use tokio::join;
 
+use crate::ui;
+use crate::Config;
+
 #[derive(Debug)]
 pub struct TopLeagues {
-    pub players: HashMap<SummonerID, (LeagueItem, Tier)>,
+    pub players: HashMap<SummonerID, (LeagueItem, Tier, PlatformRoute)>,
+    /// Position of every player on their platform's ladder, starting at 1
+    pub positions: HashMap<SummonerID, u32>,
+    /// Platforms whose ladders were fetched from the API instead of the cache
+    pub refreshed: Vec<PlatformRoute>,
+    /// Unix timestamp in seconds of when the ladders of each platform were fetched
+    pub fetched_at: HashMap<PlatformRoute, i64>,
+}
+
+/// The ladders of a platform as stored in the cache
+#[derive(serde::Deserialize, serde::Serialize)]
+struct CachedLeagues {
+    /// Unix timestamp in seconds of when the ladders were fetched
+    fetched_at: i64,
+    leagues: Vec<LeagueList>,
 }
+
 impl TopLeagues {
-    async fn get_leagues() -> Result<Vec<LeagueList>, RiotApiError> {
+    async fn get_leagues(platform: PlatformRoute) -> Result<Vec<LeagueList>, api::Error> {
+        use riven::consts::QueueType::RANKED_SOLO_5x5;
+        let route = platform.into();
+        let path = |league: &str| format!("/lol/league/v4/{league}/by-queue/{RANKED_SOLO_5x5}");
+        let (master_path, grandmaster_path, challenger_path) = (
+            path("masterleagues"),
+            path("grandmasterleagues"),
+            path("challengerleagues"),
+        );
+
         let (master, grandmaster, challenger) = join!(
-            RIOT_API.league_v4().get_master_league(
-                riven::consts::PlatformRoute::EUW1,
-                riven::consts::QueueType::RANKED_SOLO_5x5
+            api::request(
+                route,
+                &master_path,
+                RIOT_API
+                    .league_v4()
+                    .get_master_league(platform, RANKED_SOLO_5x5)
             ),
-            RIOT_API.league_v4().get_grandmaster_league(
-                riven::consts::PlatformRoute::EUW1,
-                riven::consts::QueueType::RANKED_SOLO_5x5
+            api::request(
+                route,
+                &grandmaster_path,
+                RIOT_API
+                    .league_v4()
+                    .get_grandmaster_league(platform, RANKED_SOLO_5x5)
             ),
-            RIOT_API.league_v4().get_challenger_league(
-                riven::consts::PlatformRoute::EUW1,
-                riven::consts::QueueType::RANKED_SOLO_5x5
+            api::request(
+                route,
+                &challenger_path,
+                RIOT_API
+                    .league_v4()
+                    .get_challenger_league(platform, RANKED_SOLO_5x5)
             ),
         );
 
         Ok(vec![master?, grandmaster?, challenger?])
     }
 
-    pub async fn get() -> Result<Self, RiotApiError> {
-        eprintln!("Getting top leagues...");
-        let leagues = Self::get_leagues().await?;
+    /// Gets the ladders of a platform from the cache if they are younger than the TTL, otherwise
+    /// from the API, updating the cache
+    /// # Returns
+    /// The ladders as cached, and whether they were fetched from the API
+    async fn get_leagues_cached(
+        platform: PlatformRoute,
+        config: &Config,
+    ) -> Result<(CachedLeagues, bool), api::Error> {
+        /* Recorded and replayed runs should see exactly what the API returns */
+        let use_cache = !config.refresh && api::fixture_mode().is_none();
+        let cache_file = Self::cache_file(platform, config);
 
-        let mut players: HashMap<SummonerID, (LeagueItem, Tier)> = HashMap::with_capacity(5000);
-
-        for league in leagues {
-            for entry in league.entries {
-                players.insert(entry.summoner_id.clone(), (entry, league.tier));
+        if use_cache {
+            if let Some(cached) = Self::read_cache(&cache_file) {
+                let age = Utc::now().timestamp() - cached.fetched_at;
+                if (0..config.ladder_ttl.as_secs() as i64).contains(&age) {
+                    ui::status(format!(
+                        "Using top leagues for {platform} cached {age}s ago"
+                    ));
+                    return Ok((cached, false));
+                }
             }
         }
 
-        eprintln!("Done.");
-        Ok(Self { players })
+        ui::status(format!("Getting top leagues for {platform}..."));
+        let leagues = Self::get_leagues(platform).await?;
+
+        let cached = CachedLeagues {
+            fetched_at: Utc::now().timestamp(),
+            leagues,
+        };
+        if let Err(e) = Self::write_cache(&cache_file, &cached) {
+            ui::status(format!(
+                "Error when caching top leagues for {platform}: {e}"
+            ));
+        }
+
+        Ok((cached, true))
     }
 
-    pub fn get_rank(&self, summoner_id: &str) -> Option<RankedStats> {
-        match &self.players.get(summoner_id) {
-            Some(&(ref league_item, ref tier)) => Some(RankedStats {
-                tier: tier.clone(),
-                ranked_data: league_item.clone(),
-            }),
-            None => None,
+    fn cache_file(platform: PlatformRoute, config: &Config) -> PathBuf {
+        config
+            .cache_dir
+            .join("ladders")
+            .join(format!("{platform}.json"))
+    }
+
+    fn read_cache(cache_file: &Path) -> Option<CachedLeagues> {
+        let json = std::fs::read_to_string(cache_file).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Writes the cache atomically, so that concurrent runs never read a half-written ladder
+    fn write_cache(cache_file: &Path, cached: &CachedLeagues) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = cache_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        io::write_atomically(cache_file, serde_json::to_string(cached)?.as_bytes())
+    }
+
+    /// Gets the master, grandmaster and challenger ladders of every given platform
+    pub async fn get(platforms: &[PlatformRoute], config: &Config) -> Result<Self, api::Error> {
+        let mut players: HashMap<SummonerID, (LeagueItem, Tier, PlatformRoute)> =
+            HashMap::with_capacity(5000 * platforms.len());
+
+        let mut refreshed = Vec::new();
+        let mut fetched_at = HashMap::new();
+
+        for &platform in platforms {
+            let (cached, fetched) = Self::get_leagues_cached(platform, config).await?;
+            if fetched {
+                refreshed.push(platform);
+            }
+            fetched_at.insert(platform, cached.fetched_at);
+
+            for league in cached.leagues {
+                for entry in league.entries {
+                    players.insert(entry.summoner_id.clone(), (entry, league.tier, platform));
+                }
+            }
+        }
+
+        /* Challenger first, then by LP. Wins break ties so that positions don't change between
+         * runs */
+        let mut ladder: Vec<(&SummonerID, &(LeagueItem, Tier, PlatformRoute))> =
+            players.iter().collect();
+        ladder.sort_by_key(|(id, (entry, tier, _))| {
+            (
+                std::cmp::Reverse(*tier),
+                std::cmp::Reverse(entry.league_points),
+                std::cmp::Reverse(entry.wins),
+                *id,
+            )
+        });
+        let mut next_position: HashMap<PlatformRoute, u32> = HashMap::new();
+        let mut positions = HashMap::with_capacity(players.len());
+        for (id, (_, _, platform)) in ladder {
+            let position = next_position.entry(*platform).or_insert(0);
+            *position += 1;
+            positions.insert(id.clone(), *position);
         }
+
+        ui::status("Done.".to_string());
+        Ok(Self {
+            players,
+            positions,
+            refreshed,
+            fetched_at,
+        })
     }
 
-    pub fn get_lp(&self, summoner_id: &str) -> Option<i32> {
-        self.get_rank(summoner_id)
-            .map(|rank| rank.ranked_data.league_points)
+    pub fn get_rank(&self, summoner_id: &str) -> Option<RankedStats> {
+        self.players
+            .get(summoner_id)
+            .map(|(league_item, tier, platform)| RankedStats {
+                tier: *tier,
+                ranked_data: league_item.clone(),
+                platform: *platform,
+                ladder_position: self.positions.get(summoner_id).copied().unwrap_or(0),
+            })
     }
 }
@@ -0,0 +1,486 @@
+use super::*;
+use crate::Storage;
+use chrono::Utc;
+use csv::{ReaderBuilder, WriterBuilder};
+use riven::models::match_v5::Match;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::path::PathBuf;
+
+/// Games are only checked for a result this long after they were first seen, as no game ends
+/// sooner except by a remake
+const MIN_PENDING_SECONDS: i64 = 3 * 60;
+
+/// Games that are left unresolved for this long after they were first seen are recorded without
+/// an LP change, or given up on if their result can't be fetched. No game lasts this long
+const MAX_PENDING_SECONDS: i64 = 6 * 60 * 60;
+
+/// A live game with pros in it that has not finished yet, or whose result is not recorded yet
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub(super) struct TrackedGame {
+    pub(super) game_id: i64,
+    pub(super) platform: String,
+    /// Unix timestamp in seconds of when the game was first seen
+    pub(super) first_seen: i64,
+    pub(super) pros: Vec<TrackedPro>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub(super) struct TrackedPro {
+    puuid: Puuid,
+    riot_id: String,
+    /// LP of the account when the game was seen, used to compute the LP change
+    league_points: Option<i32>,
+}
+
+/// The outcome of a finished game for one pro account
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct MatchResult {
+    pub game_id: i64,
+    pub platform: String,
+    /// Unix timestamp in seconds of when the game ended
+    pub game_end: i64,
+    pub puuid: Puuid,
+    pub riot_id: String,
+    pub champion: String,
+    pub win: bool,
+    pub kills: i32,
+    pub deaths: i32,
+    pub assists: i32,
+    /// Game duration in seconds
+    pub duration: i64,
+    pub lp_change: Option<i32>,
+}
+
+fn tracked_games_path(config: &Config) -> PathBuf {
+    config.data_dir.join("tracked_games.json")
+}
+
+fn match_history_path(config: &Config) -> PathBuf {
+    config.data_dir.join("match_history.csv")
+}
+
+pub(super) fn load_tracked_games(config: &Config) -> Result<Vec<TrackedGame>, Box<dyn Error>> {
+    if config.storage == Storage::Sqlite {
+        return db::load_tracked_games(&db::open(config)?);
+    }
+    match std::fs::read_to_string(tracked_games_path(config)) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(Box::new(e)),
+    }
+}
+
+fn save_tracked_games(config: &Config, games: &[TrackedGame]) -> Result<(), Box<dyn Error>> {
+    if config.storage == Storage::Sqlite {
+        return db::save_tracked_games(&mut db::open(config)?, games);
+    }
+    std::fs::create_dir_all(&config.data_dir)?;
+    io::write_atomically(
+        &tracked_games_path(config),
+        serde_json::to_string(games)?.as_bytes(),
+    )
+}
+
+/// Starts tracking every game found so far that isn't tracked already
+pub fn track_games(config: &Config, pro_data: &ProData) -> Result<(), Box<dyn Error>> {
+    let mut tracked = load_tracked_games(config)?;
+    let now = Utc::now().timestamp();
+
+    for game in pro_data.games() {
+        let platform = game.game_info.platform_id.clone();
+        if tracked
+            .iter()
+            .any(|t| t.game_id == game.game_id() && t.platform == platform)
+        {
+            continue;
+        }
+
+        let mut pros = Vec::new();
+        for player in &game.players {
+            let summoner_id = &player.current_game_participant.summoner_id;
+            let account = match game
+                .get_pro(summoner_id)
+                .and_then(|p| p.account(summoner_id))
+            {
+                Some(a) => a,
+                None => continue,
+            };
+            let puuid = match &account.puuid {
+                Some(p) => p,
+                None => continue,
+            };
+            pros.push(TrackedPro {
+                puuid: puuid.clone(),
+                riot_id: account.riot_id.to_string(),
+                league_points: player.get_lp(),
+            });
+        }
+
+        tracked.push(TrackedGame {
+            game_id: game.game_id(),
+            platform,
+            first_seen: now,
+            pros,
+        });
+    }
+
+    save_tracked_games(config, &tracked)
+}
+
+/// Fetches the tracked games that have finished from match-v5 and records their results. Games
+/// that are still being played, or were first seen less than `MIN_PENDING_SECONDS` ago, aren't
+/// fetched
+/// # Returns
+/// The results recorded by this call
+pub async fn resolve_finished(
+    config: &Config,
+    pro_data: &ProData,
+) -> Result<Vec<MatchResult>, Box<dyn Error>> {
+    let tracked = load_tracked_games(config)?;
+    let now = Utc::now().timestamp();
+    let live: Vec<(i64, String)> = pro_data
+        .games()
+        .iter()
+        .map(|g| (g.game_id(), g.game_info.platform_id.clone()))
+        .collect();
+    let mut pending = Vec::new();
+    let mut results = Vec::new();
+
+    for game in tracked {
+        let expired = match readiness(&game, &live, now) {
+            Readiness::Pending => {
+                pending.push(game);
+                continue;
+            }
+            Readiness::Due { expired } => expired,
+        };
+        let platform = match parse_platform(&game.platform) {
+            Ok(p) => p,
+            Err(e) => {
+                ui::status(format!("{e}, dropping tracked game {}", game.game_id));
+                continue;
+            }
+        };
+
+        let finished = match fetch_match(platform, game.game_id).await {
+            Ok(m) => m,
+            Err(e) if expired => {
+                ui::status(format!(
+                    "Error when fetching result of game {}: {e}, giving up on it",
+                    game.game_id
+                ));
+                continue;
+            }
+            Err(e) => {
+                ui::status(format!(
+                    "Error when fetching result of game {}: {e}",
+                    game.game_id
+                ));
+                pending.push(game);
+                continue;
+            }
+        };
+        let finished = match finished {
+            Some(m) => m,
+            None if expired => continue,
+            None => {
+                pending.push(game);
+                continue;
+            }
+        };
+
+        let game_end = finished
+            .info
+            .game_end_timestamp
+            .unwrap_or(finished.info.game_start_timestamp + finished.info.game_duration * 1000)
+            / 1000;
+        let ladder_is_current = ladder_is_current(
+            pro_data.top_leagues.fetched_at.get(&platform).copied(),
+            game_end,
+        );
+        if !ladder_is_current && !expired {
+            pending.push(game);
+            continue;
+        }
+
+        for tracked_pro in &game.pros {
+            let participant = match finished
+                .info
+                .participants
+                .iter()
+                .find(|p| p.puuid == tracked_pro.puuid)
+            {
+                Some(p) => p,
+                None => continue,
+            };
+            let current_lp = match ladder_is_current {
+                true => current_lp(pro_data, &tracked_pro.puuid),
+                false => None,
+            };
+
+            results.push(MatchResult {
+                game_id: game.game_id,
+                platform: game.platform.clone(),
+                game_end,
+                puuid: tracked_pro.puuid.clone(),
+                riot_id: tracked_pro.riot_id.clone(),
+                champion: participant.champion_name.clone(),
+                win: participant.win,
+                kills: participant.kills,
+                deaths: participant.deaths,
+                assists: participant.assists,
+                duration: finished.info.game_duration,
+                lp_change: current_lp
+                    .zip(tracked_pro.league_points)
+                    .map(|(now, before)| now - before),
+            });
+        }
+    }
+
+    if config.storage == Storage::Sqlite {
+        db::record_results(&mut db::open(config)?, &results, &pending)?;
+    } else {
+        /* If saving the tracked games fails, the games are resolved again next time and their
+         * results skipped by `append_results` */
+        append_results(config, &results)?;
+        save_tracked_games(config, &pending)?;
+    }
+
+    Ok(results)
+}
+
+/// Whether a tracked game can be checked for a result, see `resolve_finished`
+#[derive(Debug, PartialEq, Eq)]
+enum Readiness {
+    /// Still being played, or first seen less than `MIN_PENDING_SECONDS` ago
+    Pending,
+    /// `expired` if it was first seen more than `MAX_PENDING_SECONDS` ago
+    Due { expired: bool },
+}
+
+/// # Parameters
+/// `live` - game ID and platform of every game being played
+/// `now` - unix timestamp in seconds
+fn readiness(game: &TrackedGame, live: &[(i64, String)], now: i64) -> Readiness {
+    let is_live = live
+        .iter()
+        .any(|(id, platform)| *id == game.game_id && *platform == game.platform);
+    if is_live || now - game.first_seen < MIN_PENDING_SECONDS {
+        return Readiness::Pending;
+    }
+    Readiness::Due {
+        expired: now - game.first_seen > MAX_PENDING_SECONDS,
+    }
+}
+
+/// Whether a ladder fetched at `fetched_at` reflects the result of a game that ended at
+/// `game_end`, which is only the case if it was fetched after the game ended
+fn ladder_is_current(fetched_at: Option<i64>, game_end: i64) -> bool {
+    fetched_at.is_some_and(|fetched_at| fetched_at >= game_end)
+}
+
+async fn fetch_match(platform: PlatformRoute, game_id: i64) -> Result<Option<Match>, api::Error> {
+    let region = platform.to_regional();
+    let match_id = format!("{platform}_{game_id}");
+
+    api::request(
+        region.into(),
+        &format!("/lol/match/v5/matches/{match_id}"),
+        RIOT_API.match_v5().get_match(region, &match_id),
+    )
+    .await
+}
+
+fn current_lp(pro_data: &ProData, puuid: &str) -> Option<i32> {
+    let pro = pro_data.accounts.get(puuid)?;
+    let account = pro
+        .accounts
+        .iter()
+        .find(|a| a.puuid.as_deref() == Some(puuid))?;
+    let rank = pro_data.ranked_stats(account.summoner_id.as_ref()?)?;
+
+    Some(rank.ranked_data.league_points)
+}
+
+/// Appends results to the CSV match history, skipping those of accounts in games it already has
+fn append_results(config: &Config, results: &[MatchResult]) -> Result<(), Box<dyn Error>> {
+    let recorded: HashSet<(i64, String, Puuid)> = load_all_results(config)?
+        .into_iter()
+        .map(|r| (r.game_id, r.platform, r.puuid))
+        .collect();
+    let results: Vec<&MatchResult> = results
+        .iter()
+        .filter(|r| !recorded.contains(&(r.game_id, r.platform.clone(), r.puuid.clone())))
+        .collect();
+    if results.is_empty() {
+        return Ok(());
+    }
+
+    let path = match_history_path(config);
+    std::fs::create_dir_all(&config.data_dir)?;
+    let is_new = std::fs::metadata(&path).map_or(true, |m| m.len() == 0);
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let mut writer = WriterBuilder::new().has_headers(is_new).from_writer(file);
+    for result in results {
+        writer.serialize(result)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Loads the recorded match results of the given pro's accounts, oldest first
+pub fn load_results(config: &Config, pro: &Pro) -> Result<Vec<MatchResult>, Box<dyn Error>> {
+    let mut result = load_all_results(config)?;
+    result.retain(|r| pro.puuids().any(|puuid| *puuid == r.puuid));
+    Ok(result)
+}
+
+/// Loads the recorded match results of all accounts, oldest first
+pub(super) fn load_all_results(config: &Config) -> Result<Vec<MatchResult>, Box<dyn Error>> {
+    if config.storage == Storage::Sqlite {
+        return db::load_results(&db::open(config)?);
+    }
+
+    let file = match File::open(match_history_path(config)) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Box::new(e)),
+    };
+    let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
+
+    let mut result = Vec::new();
+    for record in reader.deserialize() {
+        let match_result: MatchResult = match record {
+            Ok(r) => r,
+            Err(e) => {
+                ui::status(format!("Error reading match history {e}, skipping line"));
+                continue;
+            }
+        };
+        result.push(match_result);
+    }
+
+    result.sort_by_key(|r| r.game_end);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn tracked_game(first_seen: i64) -> TrackedGame {
+        TrackedGame {
+            game_id: 1,
+            platform: "EUW1".to_string(),
+            first_seen,
+            pros: vec![TrackedPro {
+                puuid: "puuid-nova".to_string(),
+                riot_id: "Nova#AUR".to_string(),
+                league_points: Some(1200),
+            }],
+        }
+    }
+
+    fn match_result(game_id: i64) -> MatchResult {
+        MatchResult {
+            game_id,
+            platform: "EUW1".to_string(),
+            game_end: NOW + game_id,
+            puuid: "puuid-nova".to_string(),
+            riot_id: "Nova#AUR".to_string(),
+            champion: "Ahri".to_string(),
+            win: true,
+            kills: 5,
+            deaths: 1,
+            assists: 7,
+            duration: 1800,
+            lp_change: Some(21),
+        }
+    }
+
+    #[test]
+    fn readiness_waits_for_the_pending_window() {
+        let live = [];
+        assert_eq!(
+            readiness(&tracked_game(NOW - MIN_PENDING_SECONDS + 1), &live, NOW),
+            Readiness::Pending
+        );
+        assert_eq!(
+            readiness(&tracked_game(NOW - MIN_PENDING_SECONDS), &live, NOW),
+            Readiness::Due { expired: false }
+        );
+        assert_eq!(
+            readiness(&tracked_game(NOW - MAX_PENDING_SECONDS), &live, NOW),
+            Readiness::Due { expired: false }
+        );
+        assert_eq!(
+            readiness(&tracked_game(NOW - MAX_PENDING_SECONDS - 1), &live, NOW),
+            Readiness::Due { expired: true }
+        );
+    }
+
+    #[test]
+    fn readiness_waits_for_live_games() {
+        let game = tracked_game(NOW - 60 * 60);
+        assert_eq!(
+            readiness(&game, &[(1, "EUW1".to_string())], NOW),
+            Readiness::Pending
+        );
+        /* Game IDs are only unique per platform */
+        assert_eq!(
+            readiness(&game, &[(1, "KR".to_string())], NOW),
+            Readiness::Due { expired: false }
+        );
+    }
+
+    #[test]
+    fn ladder_is_current_after_game_end() {
+        assert!(!ladder_is_current(None, NOW));
+        assert!(!ladder_is_current(Some(NOW - 1), NOW));
+        assert!(ladder_is_current(Some(NOW), NOW));
+        assert!(ladder_is_current(Some(NOW + 1), NOW));
+    }
+
+    #[test]
+    fn append_results_skips_recorded_results() {
+        let dir = test_util::temp_dir("append_results_skips_recorded_results");
+        let config = test_util::config(&dir, &[]);
+
+        append_results(&config, &[match_result(1)]).expect("results should be appended");
+        append_results(&config, &[match_result(1), match_result(2)])
+            .expect("results should be appended");
+
+        let game_ids: Vec<i64> = load_all_results(&config)
+            .expect("results should load")
+            .iter()
+            .map(|r| r.game_id)
+            .collect();
+        assert_eq!(game_ids, [1, 2]);
+    }
+
+    #[test]
+    fn database_records_results_and_pending_games_together() {
+        let dir = test_util::temp_dir("database_records_results_and_pending_games_together");
+        let pro_file = dir.join("pros.db").to_string_lossy().to_string();
+        let config = test_util::config(&dir, &["-p", &pro_file]);
+        let other_game = TrackedGame {
+            game_id: 2,
+            ..tracked_game(NOW)
+        };
+        save_tracked_games(&config, &[tracked_game(NOW), other_game.clone()])
+            .expect("games should be tracked");
+
+        let mut connection = db::open(&config).expect("database should open");
+        db::record_results(&mut connection, &[match_result(1)], &[other_game])
+            .expect("results should be recorded");
+
+        assert_eq!(load_all_results(&config).expect("results").len(), 1);
+        let tracked = load_tracked_games(&config).expect("tracked games");
+        assert_eq!(tracked.iter().map(|g| g.game_id).collect::<Vec<_>>(), [2]);
+    }
+}
@@ -0,0 +1,270 @@
+use super::io::Row;
+use super::*;
+use crate::Storage;
+
+/// Something wrong with one row of the pro file
+#[derive(serde::Serialize)]
+pub struct Problem {
+    /// Where the row is, `file:line` for CSV files and `file#account` for databases
+    pub location: String,
+    pub message: String,
+}
+
+/// Checks every row of the pro file for malformed fields, missing IDs, duplicate accounts and
+/// inconsistent team names
+/// # Returns
+/// Every problem found, in the order of the rows they were found in
+pub fn validate(config: &Config) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let path = &config.pro_file_path;
+    let rows: Vec<(String, Result<Row, String>)> = match config.storage {
+        Storage::Csv => io::read_csv_lines(path)?
+            .into_iter()
+            .map(|(line, row)| (format!("{path}:{line}"), row))
+            .collect(),
+        Storage::Sqlite => db::load_rows(&db::open(config)?)?
+            .into_iter()
+            .map(|(id, row)| (format!("{path}#{id}"), Ok(row)))
+            .collect(),
+    };
+
+    let mut problems = Vec::new();
+    let mut problem = |location: &str, message: String| {
+        problems.push(Problem {
+            location: location.to_string(),
+            message,
+        })
+    };
+
+    /* First location each ID, Riot ID and team name was seen at */
+    let mut puuids: HashMap<String, String> = HashMap::new();
+    let mut summoner_ids: HashMap<String, String> = HashMap::new();
+    let mut riot_ids: HashMap<(String, PlatformRoute), String> = HashMap::new();
+    let mut long_names: HashMap<String, (String, String)> = HashMap::new();
+    let mut short_names: HashMap<String, (String, String)> = HashMap::new();
+
+    for (location, row) in &rows {
+        let row = match row {
+            Ok(r) => r,
+            Err(e) => {
+                problem(location, format!("malformed row: {e}"));
+                continue;
+            }
+        };
+
+        if row.pro_name.trim().is_empty() {
+            problem(location, "pro name is empty".to_string());
+        }
+        if row.short_team.trim().is_empty() || row.long_team.trim().is_empty() {
+            problem(
+                location,
+                format!(
+                    "unknown team of {}, both team names must be set",
+                    row.pro_name
+                ),
+            );
+        }
+
+        if let Err(e) = io::row_riot_id(row) {
+            problem(location, e);
+        }
+        let platform = match io::row_platform(row, config) {
+            Ok(p) => Some(p),
+            Err(e) => {
+                problem(location, e);
+                None
+            }
+        };
+
+        match (row.puuid.is_empty(), row.summoner_id.is_empty()) {
+            (false, false) => {}
+            (true, true) => problem(location, format!("{} has no IDs, run sync", row.riot_id)),
+            (true, false) => problem(location, format!("{} has no PUUID, run sync", row.riot_id)),
+            (false, true) => problem(
+                location,
+                format!("{} has no summoner ID, run sync", row.riot_id),
+            ),
+        }
+
+        let mut duplicate = |seen: &mut HashMap<String, String>, id: &str, kind: &str| {
+            if id.is_empty() {
+                return;
+            }
+            match seen.get(id) {
+                Some(first) => problem(location, format!("duplicate {kind} {id}, also at {first}")),
+                None => {
+                    seen.insert(id.to_string(), location.clone());
+                }
+            }
+        };
+        duplicate(&mut puuids, &row.puuid, "PUUID");
+        duplicate(&mut summoner_ids, &row.summoner_id, "summoner ID");
+
+        if let Some(platform) = platform {
+            let key = (row.riot_id.to_lowercase(), platform);
+            match riot_ids.get(&key) {
+                Some(first) => problem(
+                    location,
+                    format!(
+                        "duplicate Riot ID {} on {platform}, also at {first}",
+                        row.riot_id
+                    ),
+                ),
+                None => {
+                    riot_ids.insert(key, location.clone());
+                }
+            }
+        }
+
+        if row.short_team.trim().is_empty() || row.long_team.trim().is_empty() {
+            continue;
+        }
+        let short_team = row.short_team.to_uppercase();
+        match long_names.get(&short_team) {
+            Some((long_team, first)) if *long_team != row.long_team => problem(
+                location,
+                format!(
+                    "team {short_team} is called \"{}\" but \"{long_team}\" at {first}",
+                    row.long_team
+                ),
+            ),
+            Some(_) => {}
+            None => {
+                long_names.insert(
+                    short_team.clone(),
+                    (row.long_team.clone(), location.clone()),
+                );
+            }
+        }
+        match short_names.get(&row.long_team) {
+            Some((short, first)) if *short != short_team => problem(
+                location,
+                format!(
+                    "team \"{}\" is abbreviated {short_team} but {short} at {first}",
+                    row.long_team
+                ),
+            ),
+            Some(_) => {}
+            None => {
+                short_names.insert(row.long_team.clone(), (short_team, location.clone()));
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    const HEADER: &str = "pro_name,short_team,long_team,riot_id,region,puuid,summoner_id\n";
+
+    /// Validates a CSV pro file with the given rows
+    /// # Returns
+    /// The location and message of each problem, with the path of the pro file replaced by
+    /// `pros.csv`
+    fn problems(name: &str, rows: &str) -> Vec<(String, String)> {
+        let dir = test_util::temp_dir(name);
+        std::fs::write(dir.join("pros.csv"), format!("{HEADER}{rows}")).expect("should write");
+        let config = test_util::config(&dir, &[]);
+
+        let path = config.pro_file_path.as_str();
+        validate(&config)
+            .expect("pro file should be read")
+            .into_iter()
+            .map(|p| {
+                (
+                    p.location.replace(path, "pros.csv"),
+                    p.message.replace(path, "pros.csv"),
+                )
+            })
+            .collect()
+    }
+
+    fn problem(location: &str, message: &str) -> (String, String) {
+        (location.to_string(), message.to_string())
+    }
+
+    #[test]
+    fn no_problems() {
+        let rows = "Faker,T1,T1,Hide on bush#KR1,KR,p1,s1\n\
+                    Faker,T1,T1,Faker#KR2,KR,p2,s2\n\
+                    Caps,G2,G2 Esports,Caps#EUW,,p3,s3\n";
+        assert_eq!(problems("validate_no_problems", rows), []);
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let rows = "Faker,T1,T1,Hide on bush#KR1,KR,p1,s1\n\
+                    Caps,G2,G2 Esports,Caps#EUW,EUW1,p1,s2\n\
+                    Ruler,GEN,Gen.G,Ruler#KR1,KR,p3,s1\n";
+        assert_eq!(
+            problems("validate_duplicate_ids", rows),
+            [
+                problem("pros.csv:3", "duplicate PUUID p1, also at pros.csv:2"),
+                problem("pros.csv:4", "duplicate summoner ID s1, also at pros.csv:2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_riot_ids_per_platform() {
+        /* The default region is EUW1, and Riot IDs are compared case-insensitively */
+        let rows = "Caps,G2,G2 Esports,Caps#EUW,EUW1,p1,s1\n\
+                    Caps,G2,G2 Esports,caps#euw,,p2,s2\n\
+                    Caps,G2,G2 Esports,Caps#EUW,KR,p3,s3\n";
+        assert_eq!(
+            problems("validate_duplicate_riot_ids_per_platform", rows),
+            [problem(
+                "pros.csv:3",
+                "duplicate Riot ID caps#euw on EUW1, also at pros.csv:2"
+            )]
+        );
+    }
+
+    #[test]
+    fn inconsistent_team_names() {
+        let rows = "Caps,G2,G2 Esports,Caps#EUW,,p1,s1\n\
+                    Mikyx,g2,G2 Sports,Mikyx#EUW,,p2,s2\n\
+                    Hans,G2E,G2 Esports,Hans#EUW,,p3,s3\n";
+        assert_eq!(
+            problems("validate_inconsistent_team_names", rows),
+            [
+                problem(
+                    "pros.csv:3",
+                    "team G2 is called \"G2 Sports\" but \"G2 Esports\" at pros.csv:2"
+                ),
+                problem(
+                    "pros.csv:4",
+                    "team \"G2 Esports\" is abbreviated G2E but G2 at pros.csv:2"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_rows() {
+        let rows = "Faker,T1,T1,Hide on bush,KR,p1,s1\n\
+                    ,,,Caps#EUW,Atlantis,,\n\
+                    Ruler,GEN,Gen.G,Ruler#KR1,KR\n";
+        let messages: Vec<String> = problems("validate_malformed_rows", rows)
+            .into_iter()
+            .map(|(location, message)| format!("{location}: {message}"))
+            .collect();
+        assert_eq!(messages.len(), 6, "{messages:#?}");
+        assert!(messages[0].starts_with("pros.csv:2: \"Hide on bush\" is a summoner name"));
+        assert_eq!(messages[1], "pros.csv:3: pro name is empty");
+        assert_eq!(
+            messages[2],
+            "pros.csv:3: unknown team of , both team names must be set"
+        );
+        assert!(messages[3].starts_with("pros.csv:3: "), "{}", messages[3]);
+        assert_eq!(messages[4], "pros.csv:3: Caps#EUW has no IDs, run sync");
+        assert!(
+            messages[5].starts_with("pros.csv:4: malformed row: "),
+            "{}",
+            messages[5]
+        );
+    }
+}
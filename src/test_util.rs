@@ -0,0 +1,49 @@
+//! Helpers shared by the unit tests
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+
+use crate::args::Args;
+use crate::Config;
+
+/// An empty directory for one test
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lol-pros-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("should create test directory");
+    dir
+}
+
+/// Loads a config that keeps everything in `dir`, with `dir/pros.csv` as pro file unless `args`
+/// has another one. `args` are command line arguments given after the global ones.
+pub fn config(dir: &Path, args: &[&str]) -> Config {
+    let config_file = dir.join("config.toml");
+    if !config_file.exists() {
+        fs::write(&config_file, "").expect("should write config file");
+    }
+
+    Config::load(&self::args(dir, args)).expect("config should load")
+}
+
+/// Parses command line arguments that read `dir/config.toml` and keep everything in `dir`, see
+/// `config`
+pub fn args(dir: &Path, args: &[&str]) -> Args {
+    let dir = |name: &str| dir.join(name).to_string_lossy().to_string();
+    let mut all_args = vec![
+        "lol-pros".to_string(),
+        "--config".to_string(),
+        dir("config.toml"),
+        "--data-dir".to_string(),
+        dir("data"),
+        "--cache-dir".to_string(),
+        dir("cache"),
+    ];
+    if !args.contains(&"-p") {
+        all_args.extend(["-p".to_string(), dir("pros.csv")]);
+    }
+    all_args.extend(args.iter().map(|a| a.to_string()));
+
+    Args::parse_from(all_args)
+}
@@ -0,0 +1,453 @@
+//! Full-screen view of live games, the leaderboard and pro details. Games are looked up in a
+//! background task, which sends what it found to the UI after every round.
+
+mod view;
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::Arc;
+
+use chrono::{DateTime, Local};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::widgets::{ListState, TableState};
+use tokio::sync::{mpsc, Notify};
+
+use crate::pro_data::tracker::{self, MatchResult};
+use crate::pro_data::{Pro, ProData, ProGame, RankedStats};
+use crate::ui;
+use crate::watch::{self, Watcher};
+use crate::Config;
+
+/// Number of events kept for the event log
+const EVENT_LOG_LENGTH: usize = 50;
+
+/// Number of recorded results shown in the pro view
+const RESULTS_SHOWN: usize = 20;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    Games,
+    Leaderboard,
+    Pro,
+}
+
+/// A pro with the ranked stats of their ranked accounts, best first
+struct ProEntry {
+    pro: Arc<Pro>,
+    ranks: Vec<RankedStats>,
+}
+
+struct ProDetail {
+    entry_index: usize,
+    /// Recorded results, newest first
+    results: Vec<MatchResult>,
+    error: Option<String>,
+}
+
+/// Sent by the background task
+enum Message {
+    Refreshing,
+    Update {
+        games: Vec<Arc<ProGame>>,
+        pros: Vec<ProEntry>,
+        events: Vec<String>,
+    },
+    /// The background task stopped, e.g. because the API key was rejected
+    Fatal(String),
+}
+
+struct App {
+    view: View,
+    /// View to go back to from the pro view
+    previous_view: View,
+    games: Vec<Arc<ProGame>>,
+    /// Every pro, ranked pros first sorted by LP
+    pros: Vec<ProEntry>,
+    game_list: ListState,
+    leaderboard: TableState,
+    detail: Option<ProDetail>,
+    filter: String,
+    editing_filter: bool,
+    show_help: bool,
+    events: VecDeque<String>,
+    updated_at: Option<DateTime<Local>>,
+    refreshing: bool,
+    quit: bool,
+}
+
+impl App {
+    fn new() -> Self {
+        Self {
+            view: View::Games,
+            previous_view: View::Games,
+            games: Vec::new(),
+            pros: Vec::new(),
+            game_list: ListState::default().with_selected(Some(0)),
+            leaderboard: TableState::default().with_selected(Some(0)),
+            detail: None,
+            filter: String::new(),
+            editing_filter: false,
+            show_help: false,
+            events: VecDeque::new(),
+            updated_at: None,
+            refreshing: true,
+            quit: false,
+        }
+    }
+
+    /// Whether the pro's name or team contains the filter, ignoring case
+    fn matches_filter(&self, pro: &Pro) -> bool {
+        let filter = self.filter.to_lowercase();
+        [pro.name(), pro.team(), pro.team_name()]
+            .iter()
+            .any(|s| s.to_lowercase().contains(&filter))
+    }
+
+    /// Live games with a pro matching the filter
+    fn filtered_games(&self) -> Vec<&Arc<ProGame>> {
+        self.games
+            .iter()
+            .filter(|g| g.pros().iter().any(|p| self.matches_filter(p)))
+            .collect()
+    }
+
+    /// Indices into `pros` of the ranked pros matching the filter, with their leaderboard
+    /// position counted before filtering
+    fn filtered_leaderboard(&self) -> Vec<(usize, usize)> {
+        self.pros
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.ranks.is_empty())
+            .enumerate()
+            .filter(|(_, (_, e))| self.matches_filter(&e.pro))
+            .map(|(position, (i, _))| (position, i))
+            .collect()
+    }
+
+    fn selected_game(&self) -> Option<&Arc<ProGame>> {
+        let games = self.filtered_games();
+        self.game_list
+            .selected()
+            .and_then(|i| games.get(i).copied())
+    }
+
+    fn log(&mut self, event: String) {
+        self.events
+            .push_back(format!("{} {event}", Local::now().format("%X")));
+        while self.events.len() > EVENT_LOG_LENGTH {
+            self.events.pop_front();
+        }
+    }
+
+    fn handle_message(&mut self, c: &Config, message: Message) {
+        match message {
+            Message::Refreshing => self.refreshing = true,
+            Message::Update {
+                games,
+                pros,
+                events,
+            } => {
+                /* Keep the selected pro across updates */
+                let detail_pro = self
+                    .detail
+                    .as_ref()
+                    .map(|d| self.pros[d.entry_index].pro.to_string());
+
+                self.games = games;
+                self.pros = pros;
+                self.refreshing = false;
+                self.updated_at = Some(Local::now());
+                for event in events {
+                    self.log(event);
+                }
+
+                if let Some(pro) = detail_pro {
+                    match self.pros.iter().position(|e| e.pro.to_string() == pro) {
+                        Some(i) => self.detail = Some(self.load_detail(c, i)),
+                        None => {
+                            self.detail = None;
+                            if self.view == View::Pro {
+                                self.view = self.previous_view;
+                            }
+                        }
+                    }
+                }
+                self.clamp_selections();
+            }
+            Message::Fatal(_) => unreachable!("fatal messages stop the UI"),
+        }
+    }
+
+    /// Keeps the selections within the filtered lists
+    fn clamp_selections(&mut self) {
+        let clamp = |selected: Option<usize>, len: usize| {
+            Some(selected.unwrap_or(0).min(len.saturating_sub(1)))
+        };
+        let games = self.filtered_games().len();
+        let leaderboard = self.filtered_leaderboard().len();
+        self.game_list
+            .select(clamp(self.game_list.selected(), games));
+        self.leaderboard
+            .select(clamp(self.leaderboard.selected(), leaderboard));
+    }
+
+    /// Loads the recorded results of a pro
+    fn load_detail(&self, c: &Config, entry_index: usize) -> ProDetail {
+        let (results, error) = match tracker::load_results(c, &self.pros[entry_index].pro) {
+            Ok(mut results) => {
+                results.reverse();
+                results.truncate(RESULTS_SHOWN);
+                (results, None)
+            }
+            Err(e) => (
+                Vec::new(),
+                Some(format!("Error when loading match history: {e}")),
+            ),
+        };
+
+        ProDetail {
+            entry_index,
+            results,
+            error,
+        }
+    }
+
+    fn open_pro(&mut self, c: &Config, entry_index: usize) {
+        if self.view != View::Pro {
+            self.previous_view = self.view;
+        }
+        self.view = View::Pro;
+        self.detail = Some(self.load_detail(c, entry_index));
+    }
+
+    fn handle_key(&mut self, c: &Config, key: KeyEvent, refresh: &Notify) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        if self.show_help {
+            self.show_help = false;
+            return;
+        }
+
+        if self.editing_filter {
+            match key.code {
+                KeyCode::Char(ch) => self.filter.push(ch),
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Enter => self.editing_filter = false,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.editing_filter = false;
+                }
+                _ => (),
+            }
+            self.clamp_selections();
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('?') => self.show_help = true,
+            KeyCode::Char('r') => refresh.notify_one(),
+            KeyCode::Char('/') => {
+                if self.view == View::Pro {
+                    self.view = self.previous_view;
+                }
+                self.editing_filter = true;
+            }
+            KeyCode::Char('1') => self.view = View::Games,
+            KeyCode::Char('2') => self.view = View::Leaderboard,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.view = match self.view {
+                    View::Games => View::Leaderboard,
+                    View::Leaderboard | View::Pro => View::Games,
+                }
+            }
+            KeyCode::Esc | KeyCode::Backspace if self.view == View::Pro => {
+                self.view = self.previous_view;
+                self.detail = None;
+            }
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.clamp_selections();
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::Enter => self.open_selected(c),
+            _ => (),
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (state, len) = match self.view {
+            View::Games => {
+                let len = self.filtered_games().len();
+                (self.game_list.selected_mut(), len)
+            }
+            View::Leaderboard => {
+                let len = self.filtered_leaderboard().len();
+                (self.leaderboard.selected_mut(), len)
+            }
+            View::Pro => return,
+        };
+        let selected = state.unwrap_or(0).saturating_add_signed(delta);
+        *state = Some(selected.min(len.saturating_sub(1)));
+    }
+
+    /// Opens the pro view for the selected leaderboard entry, or for the first pro in the
+    /// selected game who matches the filter
+    fn open_selected(&mut self, c: &Config) {
+        let pro = match self.view {
+            View::Games => self.selected_game().and_then(|game| {
+                game.pros()
+                    .iter()
+                    .find(|p| self.matches_filter(p))
+                    .map(|p| p.to_string())
+            }),
+            View::Leaderboard => {
+                let leaderboard = self.filtered_leaderboard();
+                self.leaderboard
+                    .selected()
+                    .and_then(|i| leaderboard.get(i))
+                    .map(|&(_, i)| self.pros[i].pro.to_string())
+            }
+            View::Pro => None,
+        };
+
+        if let Some(i) = pro.and_then(|pro| self.pros.iter().position(|e| e.pro.to_string() == pro))
+        {
+            self.open_pro(c, i);
+        }
+    }
+}
+
+/// Every pro with their ranks, ranked pros first sorted by LP
+fn pro_entries(pro_data: &ProData) -> Vec<ProEntry> {
+    let mut entries: Vec<ProEntry> = pro_data
+        .get_pros()
+        .into_iter()
+        .map(|pro| ProEntry {
+            ranks: pro_data.ranked_accounts(&pro),
+            pro,
+        })
+        .collect();
+
+    entries
+        .sort_by_key(|e| std::cmp::Reverse(e.ranks.first().map(|r| r.ranked_data.league_points)));
+    entries
+}
+
+/// Looks up games in the background until the UI stops listening
+async fn refresh_games(
+    c: Config,
+    pro_data: ProData,
+    messages: mpsc::UnboundedSender<Message>,
+    refresh: Arc<Notify>,
+) {
+    let mut watcher = Watcher::new(pro_data);
+
+    loop {
+        if messages.send(Message::Refreshing).is_err() {
+            return;
+        }
+        let round = match watcher.round(&c).await {
+            Ok(r) => r,
+            Err(e) => {
+                let _ = messages.send(Message::Fatal(e));
+                return;
+            }
+        };
+
+        let mut events = round.errors;
+        if !round.first_round {
+            for game in &round.started {
+                events.push(format!("Started: {}", ui::watch::game_string(game)));
+            }
+        }
+        for game in &round.ended {
+            events.push(format!("Ended: {}", ui::watch::game_string(game)));
+        }
+        events.extend(round.results.iter().map(watch::result_event));
+
+        let update = Message::Update {
+            games: watcher.pro_data().games(),
+            pros: pro_entries(watcher.pro_data()),
+            events,
+        };
+        if messages.send(update).is_err() {
+            return;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(c.watch_interval) => (),
+            _ = refresh.notified() => (),
+        }
+    }
+}
+
+/// Runs the TUI until the user quits
+pub async fn run(c: &Config, pro_data: ProData) -> Result<(), Box<dyn Error>> {
+    /* Progress and errors would mess up the screen if printed, so they go to the event log */
+    let (status_sender, mut statuses) = mpsc::unbounded_channel();
+    ui::set_status_sender(status_sender);
+    let (message_sender, mut messages) = mpsc::unbounded_channel();
+    let refresh = Arc::new(Notify::new());
+    tokio::spawn(refresh_games(
+        c.clone(),
+        pro_data,
+        message_sender,
+        Arc::clone(&refresh),
+    ));
+
+    /* crossterm only has a blocking API without extra features, so read input on its own thread */
+    let (event_sender, mut events) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if event_sender.send(event).is_err() {
+                return;
+            }
+        }
+    });
+
+    let mut terminal = ratatui::init();
+    let mut app = App::new();
+    /* Redraw every second to keep the game clocks ticking */
+    let mut tick = tokio::time::interval(std::time::Duration::from_secs(1));
+
+    let result = loop {
+        if let Err(e) = terminal.draw(|frame| view::draw(frame, &mut app, c)) {
+            break Err(e.into());
+        }
+
+        tokio::select! {
+            Some(message) = messages.recv() => {
+                if let Message::Fatal(e) = message {
+                    break Err(e.into());
+                }
+                app.handle_message(c, message);
+            }
+            Some(status) = statuses.recv() => app.log(status),
+            /* Any event redraws, which also handles resizing */
+            Some(event) = events.recv() => {
+                if let Event::Key(key) = event {
+                    app.handle_key(c, key, &refresh);
+                }
+            }
+            _ = tick.tick() => (),
+        }
+
+        if app.quit {
+            break Ok(());
+        }
+    };
+
+    ratatui::restore();
+    result
+}
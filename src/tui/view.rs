@@ -0,0 +1,410 @@
+use chrono::{Local, TimeZone};
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Clear, List, ListItem, Paragraph, Row, Table, Tabs};
+use ratatui::Frame;
+use riven::consts::Team;
+use std::sync::Arc;
+
+use super::{App, View};
+use crate::pro_data::ProGame;
+use crate::ui::game::{self, Column, TableData};
+use crate::ui::matches;
+use crate::ui::theme::ThemeColor;
+use crate::Config;
+
+const KEYS: [(&str, &str); 11] = [
+    ("1 / 2 / Tab", "live games / leaderboard"),
+    ("j k / ↑ ↓", "select game or pro"),
+    ("PgUp / PgDn", "move selection by 10"),
+    ("Enter", "show the selected pro"),
+    ("Esc / Backspace", "back from the pro view"),
+    ("/", "filter pros and teams as you type"),
+    ("Enter", "stop typing, keeping the filter"),
+    ("Esc", "clear the filter"),
+    ("r", "look up games now"),
+    ("?", "this help"),
+    ("q / Ctrl-C", "quit"),
+];
+
+pub(super) fn draw(frame: &mut Frame, app: &mut App, c: &Config) {
+    let [tabs_area, main_area, events_area, status_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(6),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    draw_tabs(frame, app, tabs_area);
+    match app.view {
+        View::Games => draw_games(frame, app, c, main_area),
+        View::Leaderboard => draw_leaderboard(frame, app, c, main_area),
+        View::Pro => draw_pro(frame, app, c, main_area),
+    }
+    draw_events(frame, app, events_area);
+    draw_status(frame, app, status_area);
+
+    if app.show_help {
+        draw_help(frame);
+    }
+}
+
+fn selected_style() -> Style {
+    Style::new().add_modifier(Modifier::REVERSED)
+}
+
+/// A style with the given theme color, or no color if colors are disabled
+fn color_style(c: &Config, color: ThemeColor) -> Style {
+    match c.colors {
+        true => Style::new().fg(color.ratatui()),
+        false => Style::new(),
+    }
+}
+
+fn team_style(c: &Config, team: Team) -> Style {
+    color_style(c, c.theme.team(team))
+}
+
+fn draw_tabs(frame: &mut Frame, app: &App, area: Rect) {
+    let pro_tab = match &app.detail {
+        Some(detail) => format!("Pro: {}", app.pros[detail.entry_index].pro),
+        None => "Pro".to_string(),
+    };
+    let titles = vec![
+        format!("1 Live games ({})", app.filtered_games().len()),
+        format!("2 Leaderboard ({})", app.filtered_leaderboard().len()),
+        pro_tab,
+    ];
+    let selected = match app.view {
+        View::Games => 0,
+        View::Leaderboard => 1,
+        View::Pro => 2,
+    };
+
+    frame.render_widget(
+        Tabs::new(titles)
+            .select(selected)
+            .highlight_style(Style::new().add_modifier(Modifier::BOLD | Modifier::REVERSED)),
+        area,
+    );
+}
+
+/// Describes how long a game has been going on and where
+fn game_title(pro_game: &ProGame) -> String {
+    let clock = match pro_game.game_time() {
+        Some(time) => game::game_time_to_string(time),
+        None => "loading".to_string(),
+    };
+    match pro_game.platform() {
+        Some(platform) => format!("[{platform}] {clock} {}", game::average_lp_string(pro_game)),
+        None => format!("{clock} {}", game::average_lp_string(pro_game)),
+    }
+}
+
+fn draw_games(frame: &mut Frame, app: &mut App, c: &Config, area: Rect) {
+    let games: Vec<Arc<ProGame>> = app.filtered_games().into_iter().cloned().collect();
+    if games.is_empty() {
+        let message = match (app.updated_at, app.filter.is_empty()) {
+            (None, _) => "Looking for games...",
+            (Some(_), true) => "No pro is in a game",
+            (Some(_), false) => "No pro matching the filter is in a game",
+        };
+        frame.render_widget(
+            Paragraph::new(message).block(Block::bordered().title("Live games")),
+            area,
+        );
+        return;
+    }
+
+    let [list_area, game_area] =
+        Layout::horizontal([Constraint::Length(30), Constraint::Min(0)]).areas(area);
+
+    let items: Vec<ListItem> = games
+        .iter()
+        .map(|g| {
+            let pros: Vec<String> = g.pros().iter().map(|p| p.to_string()).collect();
+            ListItem::new(vec![
+                Line::from(game_title(g)),
+                Line::from(Span::styled(
+                    pros.join(", "),
+                    Style::new().add_modifier(Modifier::BOLD),
+                )),
+            ])
+        })
+        .collect();
+    let selected = app.game_list.selected().and_then(|i| games.get(i).cloned());
+    let list = List::new(items)
+        .block(Block::bordered().title("Live games"))
+        .highlight_style(selected_style());
+    frame.render_stateful_widget(list, list_area, &mut app.game_list);
+
+    if let Some(pro_game) = selected {
+        let title = format!("Game {} {}", pro_game.game_id(), game_title(&pro_game));
+        frame.render_widget(
+            game_table(&pro_game, &c.columns, c).block(Block::bordered().title(title)),
+            game_area,
+        );
+    }
+}
+
+/// A game table like `ui::game::print`, with the blue team on the left and the red team on the
+/// right
+fn game_table<'a>(pro_game: &ProGame, columns: &[Column], c: &Config) -> Table<'a> {
+    let data = TableData::new(pro_game, columns);
+    let titles: Vec<String> = columns
+        .iter()
+        .chain(columns.iter().rev())
+        .map(|column| column.to_string())
+        .collect();
+
+    let mut widths: Vec<usize> = titles
+        .iter()
+        .map(|t| Line::from(t.as_str()).width())
+        .collect();
+    for row in &data.rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(Line::from(cell.raw_string.as_str()).width());
+        }
+    }
+
+    let rows: Vec<Row> = data
+        .rows
+        .iter()
+        .map(|row| {
+            Row::new(row.iter().map(|cell| {
+                let alignment = match cell.team {
+                    Team::BLUE => Alignment::Right,
+                    _ => Alignment::Left,
+                };
+                let style = match cell.column {
+                    Column::ProName => {
+                        color_style(c, c.theme.pro(cell.team)).add_modifier(Modifier::BOLD)
+                    }
+                    _ => team_style(c, cell.team),
+                };
+                Cell::from(Line::from(cell.raw_string.clone()).alignment(alignment)).style(style)
+            }))
+        })
+        .collect();
+
+    let header = Row::new(
+        titles
+            .into_iter()
+            .map(|t| Cell::from(Line::from(t).alignment(Alignment::Center))),
+    )
+    .style(Style::new().add_modifier(Modifier::BOLD))
+    .bottom_margin(1);
+
+    Table::new(
+        rows,
+        widths.into_iter().map(|w| Constraint::Length(w as u16)),
+    )
+    .header(header)
+}
+
+fn draw_leaderboard(frame: &mut Frame, app: &mut App, c: &Config, area: Rect) {
+    let leaderboard = app.filtered_leaderboard();
+    let rows: Vec<Row> = leaderboard
+        .iter()
+        .map(|&(position, i)| {
+            let entry = &app.pros[i];
+            let rank = &entry.ranks[0];
+            let riot_id = match entry.pro.account(&rank.ranked_data.summoner_id) {
+                Some(account) => account.riot_id().to_string(),
+                None => rank.ranked_data.summoner_name.clone(),
+            };
+            let in_game = app
+                .games
+                .iter()
+                .any(|g| g.pros().iter().any(|p| Arc::ptr_eq(p, &entry.pro)));
+
+            Row::new(vec![
+                Cell::from(Line::from(format!("{}.", position + 1)).alignment(Alignment::Right)),
+                Cell::from(entry.pro.to_string()),
+                Cell::from(entry.pro.team_name().to_string()),
+                Cell::from(riot_id),
+                match c.theme.tier(rank.tier) {
+                    Some(color) => Cell::from(rank.to_string()).style(color_style(c, color)),
+                    None => Cell::from(rank.to_string()),
+                },
+                Cell::from(rank.platform.to_string()),
+                Cell::from(if in_game { "in game" } else { "" }),
+            ])
+        })
+        .collect();
+
+    let header = Row::new(vec!["#", "Pro", "Team", "Riot ID", "Rank", "Region", ""])
+        .style(Style::new().add_modifier(Modifier::BOLD));
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Min(16),
+            Constraint::Min(16),
+            Constraint::Min(20),
+            Constraint::Length(24),
+            Constraint::Length(6),
+            Constraint::Length(7),
+        ],
+    )
+    .header(header)
+    .block(Block::bordered().title("Leaderboard"))
+    .row_highlight_style(selected_style());
+
+    frame.render_stateful_widget(table, area, &mut app.leaderboard);
+}
+
+fn draw_pro(frame: &mut Frame, app: &App, c: &Config, area: Rect) {
+    let detail = match &app.detail {
+        Some(d) => d,
+        None => return,
+    };
+    let entry = &app.pros[detail.entry_index];
+    let pro = &entry.pro;
+    let block = Block::bordered().title(format!("{pro} - {}", pro.team_name()));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [accounts_area, game_area, results_area] = Layout::vertical([
+        Constraint::Length(pro.accounts().len() as u16 + 2),
+        Constraint::Length(2),
+        Constraint::Min(0),
+    ])
+    .areas(inner);
+
+    let rows: Vec<Row> = pro
+        .accounts()
+        .iter()
+        .map(|account| {
+            let rank = entry
+                .ranks
+                .iter()
+                .find(|r| Some(r.ranked_data.summoner_id.as_str()) == account.summoner_id());
+            Row::new(vec![
+                account.riot_id().to_string(),
+                account.platform().to_string(),
+                match rank {
+                    Some(rank) => rank.to_string(),
+                    None => "-".to_string(),
+                },
+            ])
+        })
+        .collect();
+    let header = Row::new(vec!["Account", "Region", "Rank"])
+        .style(Style::new().add_modifier(Modifier::BOLD));
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Min(20),
+                Constraint::Length(6),
+                Constraint::Length(24),
+            ],
+        )
+        .header(header),
+        accounts_area,
+    );
+
+    let live_game = app
+        .games
+        .iter()
+        .find(|g| g.pros().iter().any(|p| Arc::ptr_eq(p, pro)));
+    let game_line = match live_game {
+        Some(pro_game) => {
+            let champion = pro
+                .summoner_ids()
+                .find_map(|id| pro_game.get_player(id))
+                .and_then(|p| p.current_game_participant.champion_id.name())
+                .unwrap_or("Unknown");
+            Line::from(vec![
+                Span::styled("In game ", team_style(c, Team::BLUE)),
+                Span::raw(format!("as {champion}, {}", game_title(pro_game))),
+            ])
+        }
+        None => Line::from("Not in a live game"),
+    };
+    frame.render_widget(Paragraph::new(game_line), game_area);
+
+    let mut lines = vec![Line::from(Span::styled(
+        "Recent games",
+        Style::new().add_modifier(Modifier::BOLD),
+    ))];
+    if let Some(error) = &detail.error {
+        lines.push(Line::from(error.as_str()));
+    } else if detail.results.is_empty() {
+        lines.push(Line::from("No recorded games"));
+    }
+    for result in &detail.results {
+        let time = match Local.timestamp_opt(result.game_end, 0).single() {
+            Some(t) => t.format("%Y-%m-%d %H:%M").to_string(),
+            None => result.game_end.to_string(),
+        };
+        let style = match (c.colors, result.win) {
+            (false, _) => Style::new(),
+            (true, true) => Style::new().fg(Color::Green),
+            (true, false) => Style::new().fg(Color::Red),
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!("{time} {} ", result.riot_id)),
+            Span::styled(matches::result_string(result), style),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines), results_area);
+}
+
+fn draw_events(frame: &mut Frame, app: &App, area: Rect) {
+    let shown = area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = app
+        .events
+        .iter()
+        .skip(app.events.len().saturating_sub(shown))
+        .map(|e| Line::from(e.as_str()))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title("Events")),
+        area,
+    );
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let line = if app.editing_filter {
+        Line::from(format!("Filter: {}_", app.filter))
+    } else {
+        let mut status = Vec::new();
+        if !app.filter.is_empty() {
+            status.push(format!("Filter: {} (Esc to clear)", app.filter));
+        }
+        match (app.refreshing, app.updated_at) {
+            (true, _) => status.push("Looking up games...".to_string()),
+            (false, Some(t)) => status.push(format!("Updated {}", t.format("%X"))),
+            (false, None) => (),
+        }
+        status.push("? help, q quit".to_string());
+        Line::from(status.join(" | "))
+    };
+
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+fn draw_help(frame: &mut Frame) {
+    let width = 56.min(frame.area().width);
+    let height = (KEYS.len() as u16 + 2).min(frame.area().height);
+    let area = Rect {
+        x: frame.area().width.saturating_sub(width) / 2,
+        y: frame.area().height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+
+    let rows = KEYS
+        .iter()
+        .map(|(key, action)| Row::new(vec![*key, *action]));
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Table::new(rows, [Constraint::Length(16), Constraint::Min(0)])
+            .block(Block::bordered().title("Keys (any key to close)")),
+        area,
+    );
+}
@@ -1,5 +1,191 @@
-use prettytable::{format, row, Table};
-use crate::pro_data::ProData;
-
 pub mod game;
+pub mod history;
+pub mod json;
 pub mod leaderboard;
+pub mod matches;
+pub mod teams;
+pub mod theme;
+pub mod watch;
+
+use std::sync::OnceLock;
+
+use prettytable::Table;
+use tokio::sync::mpsc;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+use yansi::Paint;
+
+use theme::Theme;
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Where `status` sends messages instead of stderr, set while the TUI owns the terminal
+static STATUS_SENDER: OnceLock<mpsc::UnboundedSender<String>> = OnceLock::new();
+
+/// How command results are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Tables for humans
+    Table,
+    /// One JSON document per command, see `ui::json` for the schema
+    Json,
+    /// One JSON document per line as results come in
+    Ndjson,
+}
+
+/// When output is colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorWhen {
+    /// If stdout is a terminal, unless NO_COLOR is set or CLICOLOR is 0. CLICOLOR_FORCE forces
+    /// colors
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorWhen {
+    /// Whether output should be colored
+    /// # Parameters
+    /// `var` - looks up an environment variable
+    /// `is_terminal` - whether stdout is a terminal
+    pub fn enabled(self, var: impl Fn(&str) -> Option<String>, is_terminal: bool) -> bool {
+        let var = |name| var(name).filter(|v| !v.is_empty());
+        match self {
+            ColorWhen::Always => true,
+            ColorWhen::Never => false,
+            ColorWhen::Auto if var("NO_COLOR").is_some() => false,
+            ColorWhen::Auto if var("CLICOLOR_FORCE").is_some_and(|v| v != "0") => true,
+            ColorWhen::Auto if var("CLICOLOR").is_some_and(|v| v == "0") => false,
+            ColorWhen::Auto => is_terminal,
+        }
+    }
+}
+
+/// Enables or disables colors and styles in every renderer
+pub fn set_colors(enabled: bool) {
+    match enabled {
+        true => Paint::enable(),
+        false => Paint::disable(),
+    }
+}
+
+pub fn colors() -> bool {
+    Paint::is_enabled()
+}
+
+pub fn set_theme(theme: Theme) {
+    THEME.set(theme).expect("theme should only be set once");
+}
+
+/// The theme from the config file, or the default one
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
+
+/// Sends every later `status` message to the receiver, until it is dropped
+pub fn set_status_sender(sender: mpsc::UnboundedSender<String>) {
+    STATUS_SENDER
+        .set(sender)
+        .expect("status sender should only be set once");
+}
+
+/// Reports progress or an error that doesn't stop the program. Printed to stderr, unless the TUI
+/// is running, which shows it in its event log instead
+pub fn status(message: String) {
+    let message = match STATUS_SENDER.get() {
+        Some(sender) => match sender.send(message) {
+            Ok(()) => return,
+            /* The TUI has stopped */
+            Err(unsent) => unsent.0,
+        },
+        None => message,
+    };
+    eprintln!("{message}");
+}
+
+/// Number of terminal columns a string takes up. Each grapheme cluster, e.g. a letter with
+/// combining accents or a sequence of emoji, takes up at most two columns, and CJK characters take
+/// up two
+pub fn display_width(s: &str) -> usize {
+    s.graphemes(true).map(|g| g.width().min(2)).sum()
+}
+
+/// Prints a table to stdout, with colors and styles only if they are enabled
+pub fn print_table(table: &Table) {
+    /* prettytable colors tables by itself when stdout is a terminal, so it has to be told both
+     * ways. Like `Table::printstd`, errors are ignored. */
+    let _ = match colors() {
+        true => table.print_tty(true),
+        false => table.print(&mut std::io::stdout()),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{display_width, ColorWhen};
+
+    #[test]
+    fn display_width_of_ascii() {
+        assert_eq!(display_width(""), 0);
+        assert_eq!(display_width("Faker"), 5);
+    }
+
+    #[test]
+    fn display_width_of_cjk() {
+        assert_eq!(display_width("페이커"), 6);
+        assert_eq!(display_width("小明"), 4);
+        assert_eq!(display_width("Hide on 부시"), 12);
+    }
+
+    #[test]
+    fn display_width_of_combining_characters() {
+        assert_eq!(display_width("Jose\u{301}"), 4);
+        assert_eq!(display_width("Zoe\u{308}"), display_width("Zoë"));
+        assert_eq!(display_width("a\u{301}\u{302}\u{303}"), 1);
+    }
+
+    #[test]
+    fn display_width_of_emoji_sequences() {
+        /* Family, thumbs up with a skin tone and a flag, each shown as one emoji */
+        assert_eq!(
+            display_width("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}"),
+            2
+        );
+        assert_eq!(display_width("\u{1F44D}\u{1F3FD}"), 2);
+        assert_eq!(display_width("\u{1F1F8}\u{1F1EA}"), 2);
+        assert_eq!(display_width("GG\u{1F44D}\u{1F3FD}"), 4);
+    }
+
+    #[test]
+    fn color_when_enabled() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let enabled = |when: ColorWhen, vars, is_terminal| when.enabled(env(vars), is_terminal);
+
+        assert!(enabled(ColorWhen::Always, &[("NO_COLOR", "1")], false));
+        assert!(!enabled(ColorWhen::Never, &[("CLICOLOR_FORCE", "1")], true));
+        assert!(enabled(ColorWhen::Auto, &[], true));
+        assert!(!enabled(ColorWhen::Auto, &[], false));
+        assert!(!enabled(
+            ColorWhen::Auto,
+            &[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")],
+            true
+        ));
+        assert!(enabled(ColorWhen::Auto, &[("NO_COLOR", "")], true));
+        assert!(enabled(ColorWhen::Auto, &[("CLICOLOR_FORCE", "1")], false));
+        assert!(!enabled(ColorWhen::Auto, &[("CLICOLOR_FORCE", "0")], false));
+        assert!(enabled(
+            ColorWhen::Auto,
+            &[("CLICOLOR_FORCE", "1"), ("CLICOLOR", "0")],
+            false
+        ));
+        assert!(!enabled(ColorWhen::Auto, &[("CLICOLOR", "0")], true));
+        assert!(enabled(ColorWhen::Auto, &[("CLICOLOR", "1")], true));
+    }
+}
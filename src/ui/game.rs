@@ -1,71 +1,37 @@
 use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
 
-use crate::pro_data::{Player, ProGame};
+use crate::pro_data::{Player, ProGame, RankedStats};
+use crate::ui::{self, theme::ThemeColor};
 
-use enum_iterator::Sequence;
-use lazy_static::lazy_static;
 use prettytable::format::{self, Alignment};
-use prettytable::{self, color, Attr, Cell, Row, Table};
+use prettytable::{self, Attr, Cell, Row, Table};
 use riven::consts::Team;
+use yansi::Paint;
 
-struct TableData {
-    rows: Vec<Vec<CellData>>,
+/// The cells of a game table, shared by the printed table and the TUI
+pub struct TableData {
+    /// One row per pair of blue and red players, with the blue player's cells first
+    pub rows: Vec<Vec<CellData>>,
 }
 
 impl TableData {
-    fn new(pro_game: &ProGame) -> TableData {
+    pub fn new(pro_game: &ProGame, columns: &[Column]) -> TableData {
         let mut cells: Vec<Vec<CellData>> = Vec::new();
         let (blue_team, red_team) = pro_game.teams();
 
         for (blue_participant, red_participant) in red_team.iter().zip(blue_team.iter()) {
             let f = |player: &Player| {
-                let mut player_row = Vec::new();
-                let summoner_id = &player.current_game_participant.summoner_id;
-                let summoner_name = &player.current_game_participant.summoner_name;
-                let pro_name = pro_game.get_pro(&summoner_id);
-                let champion_name = player.current_game_participant.champion_id.name().unwrap();
-
-                for column in ALL_COLUMNS.iter().rev() {
-                    match column {
-                        Column::ProName => {
-                            player_row.push(CellData {
-                                team: player.current_game_participant.team_id,
-                                column: Column::ProName,
-                                raw_string: match pro_name {
-                                    Some(pro) => pro.to_string(),
-                                    None => "".to_string(),
-                                },
-                            });
-                        }
-                        Column::RankInfo => {
-                            let rank_str = match player.ranked_stats() {
-                                Some(rank) => rank.to_string(),
-                                None => "-".to_string(),
-                            };
-                            player_row.push(CellData {
-                                team: player.current_game_participant.team_id,
-                                column: Column::RankInfo,
-                                raw_string: rank_str,
-                            });
-                        }
-                        Column::SummonerName => {
-                            player_row.push(CellData {
-                                team: player.current_game_participant.team_id,
-                                column: Column::SummonerName,
-                                raw_string: summoner_name.trim_end().to_string(),
-                            });
-                        }
-                        Column::ChampionName => {
-                            player_row.push(CellData {
-                                team: player.current_game_participant.team_id,
-                                column: Column::ChampionName,
-                                raw_string: champion_name.to_string(),
-                            });
-                        }
-                    }
-                }
-
-                player_row
+                columns
+                    .iter()
+                    .rev()
+                    .map(|column| CellData {
+                        team: player.current_game_participant.team_id,
+                        column: *column,
+                        raw_string: column.value(pro_game, player),
+                    })
+                    .collect::<Vec<CellData>>()
             };
 
             let (mut blue_player, mut red_player) = (f(blue_participant), f(red_participant));
@@ -78,11 +44,11 @@ impl TableData {
         Self { rows: cells }
     }
 
-    fn get_column_lengths(&self) -> Vec<usize> {
+    fn get_column_lengths(&self, columns: &[Column]) -> Vec<usize> {
         let mut column_lengths = Vec::new();
         const PADDING: usize = 1;
 
-        for column in ALL_COLUMNS.iter() {
+        for column in columns.iter() {
             let max_length = self
                 .rows
                 .iter()
@@ -93,16 +59,16 @@ impl TableData {
 
             column_lengths.push(max_length);
         }
-        assert_eq!(column_lengths.len(), ALL_COLUMNS.len());
+        assert_eq!(column_lengths.len(), columns.len());
 
         column_lengths
     }
 
-    fn get_title_row() -> Row {
+    fn get_title_row(columns: &[Column]) -> Row {
         let mut column_strings: Vec<String> = Vec::new();
         let mut result = Vec::new();
 
-        for column in ALL_COLUMNS.iter() {
+        for column in columns.iter() {
             column_strings.push(column.to_string());
         }
 
@@ -123,49 +89,184 @@ impl TableData {
         Row::new(result)
     }
 
-    fn print(&self) {
-        let column_lengths = self.get_column_lengths();
+    /// Resolves `Layout::Auto` to the widest layout that fits in `width` columns, side-by-side if
+    /// the width is unknown. Other layouts are kept as they are.
+    fn layout(&self, columns: &[Column], layout: Layout, width: Option<usize>) -> Layout {
+        match (layout, width) {
+            (Layout::Auto, Some(width)) => {
+                if self.width(columns) <= width {
+                    Layout::SideBySide
+                } else if self.stacked_width(columns) <= width {
+                    Layout::Stacked
+                } else {
+                    Layout::Compact
+                }
+            }
+            (Layout::Auto, None) => Layout::SideBySide,
+            (layout, _) => layout,
+        }
+    }
+
+    /// Width of the side-by-side table
+    fn width(&self, columns: &[Column]) -> usize {
+        let lengths = self
+            .get_column_lengths(columns)
+            .into_iter()
+            .zip(columns)
+            .map(|(length, column)| length.max(ui::display_width(&column.to_string())));
+        /* Both teams have every column */
+        2 * table_width(lengths) - 1
+    }
+
+    /// Width of each team's table when stacked
+    fn stacked_width(&self, columns: &[Column]) -> usize {
+        let lengths = columns.iter().map(|column| {
+            self.team_rows(Team::BLUE, columns)
+                .chain(self.team_rows(Team::RED, columns))
+                .flat_map(|row| row.into_iter().filter(|cell| cell.column == *column))
+                .map(|cell| cell.get_str_length())
+                .chain([ui::display_width(&column.to_string())])
+                .max()
+                .unwrap_or(0)
+        });
+        table_width(lengths)
+    }
+
+    /// The cells of each player in a team, in the order of `columns`
+    fn team_rows<'a>(
+        &'a self,
+        team: Team,
+        columns: &[Column],
+    ) -> impl Iterator<Item = Vec<&'a CellData>> {
+        let n = columns.len();
+        self.rows.iter().map(move |row| match team {
+            Team::BLUE => row[..n].iter().collect(),
+            _ => row[n..].iter().rev().collect(),
+        })
+    }
+
+    /// Prints a table for the blue team followed by one for the red team
+    fn print_stacked(&self, columns: &[Column]) {
+        for table in self.stacked_tables(columns) {
+            ui::print_table(&table);
+        }
+    }
+
+    /// The table of the blue team and the one of the red team
+    fn stacked_tables(&self, columns: &[Column]) -> [Table; 2] {
+        [Team::BLUE, Team::RED].map(|team| {
+            let mut table = Table::new();
+            table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+            table.set_titles(Row::new(
+                columns
+                    .iter()
+                    .map(|column| {
+                        let mut c = Cell::new(&column.to_string());
+                        c.style(Attr::Bold);
+                        c
+                    })
+                    .collect(),
+            ));
+
+            for row in self.team_rows(team, columns) {
+                table.add_row(Row::new(
+                    row.into_iter()
+                        .map(|cell| {
+                            let mut c = Cell::new(&cell.raw_string);
+                            c.style(Attr::ForegroundColor(cell.color().term()));
+                            c
+                        })
+                        .collect(),
+                ));
+            }
+            table
+        })
+    }
+
+    /// Prints one line per player, blue team first, with `-` for empty cells
+    fn print_compact(&self, columns: &[Column]) {
+        for line in self.compact_lines(columns) {
+            println!("{line}");
+        }
+    }
+
+    /// The lines printed by `print_compact`
+    fn compact_lines(&self, columns: &[Column]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (team, name) in [(Team::BLUE, "Blue"), (Team::RED, "Red")] {
+            lines.push(Paint::new(name).bold().to_string());
+            for row in self.team_rows(team, columns) {
+                /* Empty fields get a placeholder, so every value keeps its position */
+                let values: Vec<String> = row
+                    .iter()
+                    .map(|cell| match cell.raw_string.is_empty() {
+                        true => "-".to_string(),
+                        false => Paint::new(&cell.raw_string)
+                            .fg(cell.color().yansi())
+                            .to_string(),
+                    })
+                    .collect();
+                lines.push(format!("  {}", values.join(" | ")));
+            }
+        }
+        lines
+    }
+
+    fn print(&self, columns: &[Column]) {
+        assert_eq!(self.rows.len(), 5);
+        ui::print_table(&self.table(columns));
+    }
+
+    /// The side-by-side table, with every cell padded to the display width of its column
+    fn table(&self, columns: &[Column]) -> Table {
+        let column_lengths = self.get_column_lengths(columns);
         let mut table = Table::new();
         table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
-        table.set_titles(Self::get_title_row());
+        table.set_titles(Self::get_title_row(columns));
 
-        assert_eq!(self.rows.len(), 5);
         for row in &self.rows {
             let cells = {
                 let mut v = Vec::new();
                 for cell in row {
-                    v.push(cell.make_cell(column_lengths[cell.clone().column as usize]))
+                    let index = columns.iter().position(|c| *c == cell.column);
+                    v.push(cell.make_cell(column_lengths[index.expect("cell has a column")]))
                 }
                 v
             };
 
             table.add_row(Row::new(cells));
         }
-        table.printstd();
+        table
     }
 }
 
+/// Width of a table with the given column content lengths, counting padding and borders
+fn table_width(lengths: impl Iterator<Item = usize>) -> usize {
+    /* One space of padding on each side and a border to the left of each column, plus the
+     * border on the right */
+    lengths.map(|length| length + 3).sum::<usize>() + 1
+}
+
 #[derive(Debug)]
-struct CellData {
-    team: Team,
-    column: Column,
-    raw_string: String,
+pub struct CellData {
+    pub team: Team,
+    pub column: Column,
+    pub raw_string: String,
 }
 
 impl CellData {
     fn make_cell(&self, length: usize) -> Cell {
-        assert!(length + 1 >= self.raw_string.len());
+        assert!(length + 1 >= self.get_str_length());
 
-        let whitespace_to_add = length - self.raw_string.len();
+        let whitespace_to_add = length - self.get_str_length();
 
         let mut s = String::new();
-        match self.team {
+        let mut cell = match self.team {
             Team::BLUE => {
                 s.push_str(" ".repeat(whitespace_to_add).as_str());
                 s.push_str(self.raw_string.as_str());
                 let mut cell = Cell::new(s.as_str());
                 cell.align(Alignment::RIGHT);
-                cell.style(Attr::ForegroundColor(color::BLUE));
                 cell
             }
             Team::RED => {
@@ -173,29 +274,42 @@ impl CellData {
                 s.push_str(" ".repeat(whitespace_to_add).as_str());
                 let mut cell = Cell::new(s.as_str());
                 cell.align(Alignment::LEFT);
-                cell.style(Attr::ForegroundColor(color::RED));
                 cell
             }
             Team::OTHER => panic!("Summoner should be BLUE or RED team"),
+        };
+        cell.style(Attr::ForegroundColor(self.color().term()));
+        cell
+    }
+
+    /// The theme color of the cell, which depends on the team and whether it is a pro's name
+    pub fn color(&self) -> ThemeColor {
+        match self.column {
+            Column::ProName => ui::theme().pro(self.team),
+            _ => ui::theme().team(self.team),
         }
     }
 
+    /// Display width of the cell's text
     fn get_str_length(&self) -> usize {
-        self.raw_string.len()
+        ui::display_width(&self.raw_string)
     }
 }
 
-lazy_static! {
-    static ref ALL_COLUMNS: Vec<Column> = enum_iterator::all::<Column>().collect::<Vec<_>>();
-}
-
-#[derive(Sequence, Eq, PartialEq, Debug, Copy, Clone)]
-/// Columns ordered from left to right
-enum Column {
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum Column {
     ProName,
     RankInfo,
     SummonerName,
     ChampionName,
+    SummonerSpells,
+    Keystone,
+    Tier,
+    Winrate,
+    GamesPlayed,
+    LadderPosition,
+    HotStreak,
+    Region,
 }
 
 impl Display for Column {
@@ -205,19 +319,370 @@ impl Display for Column {
             Column::RankInfo => write!(f, "Rank"),
             Column::SummonerName => write!(f, "Summoner"),
             Column::ChampionName => write!(f, "Champion"),
+            Column::SummonerSpells => write!(f, "Spells"),
+            Column::Keystone => write!(f, "Keystone"),
+            Column::Tier => write!(f, "Tier"),
+            Column::Winrate => write!(f, "WR"),
+            Column::GamesPlayed => write!(f, "Games"),
+            Column::LadderPosition => write!(f, "#"),
+            Column::HotStreak => write!(f, "Streak"),
+            Column::Region => write!(f, "Region"),
         }
     }
 }
 
-pub async fn print(pro_game: &ProGame) -> Result<(), ()> {
-    let width = termsize::get().map(|size| size.cols);
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowercase = s.to_lowercase();
+        match Column::NAMES.iter().find(|(name, _)| *name == lowercase) {
+            Some((_, column)) => Ok(*column),
+            None => {
+                let names: Vec<&str> = Column::NAMES.iter().map(|(name, _)| *name).collect();
+                Err(format!(
+                    "Unknown column \"{s}\", expected one of {}",
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+}
 
-    let separator = "—".repeat(width.unwrap_or(120) as usize);
+impl Column {
+    /// Name of each column in `--columns` and the config file
+    const NAMES: [(&'static str, Column); 12] = [
+        ("pro", Column::ProName),
+        ("rank", Column::RankInfo),
+        ("summoner", Column::SummonerName),
+        ("champion", Column::ChampionName),
+        ("spells", Column::SummonerSpells),
+        ("keystone", Column::Keystone),
+        ("tier", Column::Tier),
+        ("winrate", Column::Winrate),
+        ("games", Column::GamesPlayed),
+        ("position", Column::LadderPosition),
+        ("streak", Column::HotStreak),
+        ("region", Column::Region),
+    ];
+
+    /// Name of the column in `--columns` and the config file
+    pub fn name(&self) -> &'static str {
+        Column::NAMES
+            .iter()
+            .find(|(_, column)| column == self)
+            .map(|(name, _)| *name)
+            .expect("every column has a name")
+    }
+
+    /// The columns shown when none are configured
+    pub fn defaults() -> Vec<Column> {
+        vec![
+            Column::ProName,
+            Column::RankInfo,
+            Column::SummonerName,
+            Column::ChampionName,
+        ]
+    }
+
+    /// The text of this column for a player, `-` for ranked columns of players below master
+    fn value(&self, pro_game: &ProGame, player: &Player) -> String {
+        let participant = &player.current_game_participant;
+        let rank = player.ranked_stats().as_ref();
+        let rank_value = |f: &dyn Fn(&RankedStats) -> String| match rank {
+            Some(rank) => f(rank),
+            None => "-".to_string(),
+        };
+
+        match self {
+            Column::ProName => match pro_game.get_pro(&participant.summoner_id) {
+                Some(pro) => pro.to_string(),
+                None => "".to_string(),
+            },
+            Column::RankInfo => rank_value(&|r| r.to_string()),
+            Column::SummonerName => participant.summoner_name.trim_end().to_string(),
+            Column::ChampionName => participant
+                .champion_id
+                .name()
+                .unwrap_or("Unknown")
+                .to_string(),
+            Column::SummonerSpells => format!(
+                "{} {}",
+                spell_name(participant.spell1_id),
+                spell_name(participant.spell2_id)
+            ),
+            Column::Keystone => match participant.perks.as_ref().and_then(|p| p.perk_ids.first()) {
+                Some(&id) => keystone_name(id),
+                None => "-".to_string(),
+            },
+            Column::Tier => rank_value(&|r| r.tier_abbreviation().to_string()),
+            Column::Winrate => rank_value(&|r| {
+                r.winrate()
+                    .map_or("-".to_string(), |winrate| format!("{winrate:.1}%"))
+            }),
+            Column::GamesPlayed => rank_value(&|r| r.games_played().to_string()),
+            Column::LadderPosition => rank_value(&|r| r.ladder_position.to_string()),
+            Column::HotStreak => match rank {
+                Some(r) if r.ranked_data.hot_streak => "hot".to_string(),
+                _ => "".to_string(),
+            },
+            Column::Region => match pro_game.platform() {
+                Some(platform) => platform.to_string(),
+                None => "-".to_string(),
+            },
+        }
+    }
+}
+
+/// Name of a summoner spell, or its ID if it isn't one played on Summoner's Rift
+fn spell_name(id: i64) -> String {
+    let name = match id {
+        1 => "Cleanse",
+        3 => "Exhaust",
+        4 => "Flash",
+        6 => "Ghost",
+        7 => "Heal",
+        11 => "Smite",
+        12 => "Teleport",
+        14 => "Ignite",
+        21 => "Barrier",
+        _ => return id.to_string(),
+    };
+    name.to_string()
+}
+
+/// Name of a keystone rune, or its ID if it is unknown
+fn keystone_name(id: i64) -> String {
+    let name = match id {
+        8005 => "Press the Attack",
+        8008 => "Lethal Tempo",
+        8010 => "Conqueror",
+        8021 => "Fleet Footwork",
+        8112 => "Electrocute",
+        8124 => "Predator",
+        8128 => "Dark Harvest",
+        9923 => "Hail of Blades",
+        8214 => "Summon Aery",
+        8229 => "Arcane Comet",
+        8230 => "Phase Rush",
+        8437 => "Grasp of the Undying",
+        8439 => "Aftershock",
+        8465 => "Guardian",
+        8351 => "Glacial Augment",
+        8360 => "Unsealed Spellbook",
+        8369 => "First Strike",
+        _ => return id.to_string(),
+    };
+    name.to_string()
+}
+
+/// How a game is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    /// The widest layout that fits in the terminal
+    Auto,
+    /// One table with the blue team on the left and the red team on the right
+    SideBySide,
+    /// A table for the blue team above one for the red team
+    Stacked,
+    /// One line per player
+    Compact,
+}
+
+/// Prints a game with the blue team on the left and the red team on the right, or in the layout
+/// given
+/// # Parameters
+/// `columns`: columns of each team, from the edge of the table to the middle
+/// `layout`: with `Layout::Auto` the widest layout that fits the terminal is used, side-by-side if
+/// the width of the terminal is unknown
+pub async fn print(pro_game: &ProGame, columns: &[Column], layout: Layout) -> Result<(), ()> {
+    let width = termsize::get().map(|size| size.cols as usize);
+    let table_data = TableData::new(pro_game, columns);
+    let layout = table_data.layout(columns, layout, width);
+
+    let separator = "—".repeat(width.unwrap_or(120));
+
+    let clock = match pro_game.game_time() {
+        Some(time) => format!(" {}", game_time_to_string(time)),
+        None => " loading".to_string(),
+    };
 
     println!("{separator}");
-    eprintln!("{}LP", pro_game.average_lp());
-    TableData::new(pro_game).print();
+    match pro_game.platform() {
+        Some(platform) => println!("[{platform}] {}{clock}", average_lp_string(pro_game)),
+        None => println!("{}{clock}", average_lp_string(pro_game)),
+    }
+    match layout {
+        Layout::Stacked => table_data.print_stacked(columns),
+        Layout::Compact => table_data.print_compact(columns),
+        _ => table_data.print(columns),
+    }
     println!("{separator}");
 
     Ok(())
 }
+
+/// Formats the average LP of a game, e.g. `1204LP`, or `-` if no player is ranked
+pub fn average_lp_string(pro_game: &ProGame) -> String {
+    match pro_game.average_lp() {
+        Some(lp) => format!("{lp}LP"),
+        None => "-".to_string(),
+    }
+}
+
+/// Formats a game time as `mm:ss`, or `h:mm:ss` from an hour on
+pub fn game_time_to_string(game_time: Duration) -> String {
+    let seconds = game_time.as_secs();
+    match seconds / 3600 {
+        0 => format!("{:02}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{hours}:{:02}:{:02}", seconds / 60 % 60, seconds % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: [Column; 2] = [Column::SummonerName, Column::ChampionName];
+
+    /// A game table with one player per team in each row
+    fn table_data(players: &[(&str, &str, &str, &str)]) -> TableData {
+        let cell = |team, column, raw_string: &str| CellData {
+            team,
+            column,
+            raw_string: raw_string.to_string(),
+        };
+        let rows = players
+            .iter()
+            .map(|(blue_name, blue_champion, red_champion, red_name)| {
+                vec![
+                    cell(Team::BLUE, Column::SummonerName, blue_name),
+                    cell(Team::BLUE, Column::ChampionName, blue_champion),
+                    cell(Team::RED, Column::ChampionName, red_champion),
+                    cell(Team::RED, Column::SummonerName, red_name),
+                ]
+            })
+            .collect();
+        TableData { rows }
+    }
+
+    #[test]
+    fn table_pads_by_display_width() {
+        let table_data = table_data(&[
+            ("Faker", "Ahri", "Azir", "Caps"),
+            ("페이커", "Ahri", "Azir", "小明"),
+            ("Jose\u{301}", "Akali", "Ashe", "Zoe\u{308}"),
+            (
+                "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}",
+                "Amumu",
+                "Anivia",
+                "\u{1F44D}\u{1F3FD}",
+            ),
+            ("\u{1F1F8}\u{1F1EA}", "Annie", "Aatrox", "x"),
+        ]);
+
+        let table = table_data.table(&COLUMNS).to_string();
+        assert_eq!(
+            table,
+            concat!(
+                "+----------+----------+----------+----------+\n",
+                "| Summoner | Champion | Champion | Summoner |\n",
+                "+----------+----------+----------+----------+\n",
+                "|    Faker |     Ahri | Azir     | Caps     |\n",
+                "|   페이커 |     Ahri | Azir     | 小明     |\n",
+                "|     Jose\u{301} |    Akali | Ashe     | Zoe\u{308}      |\n",
+                "|       \u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467} |    Amumu | ",
+                "Anivia   | \u{1F44D}\u{1F3FD}       |\n",
+                "|       \u{1F1F8}\u{1F1EA} |    Annie | Aatrox   | x        |\n",
+                "+----------+----------+----------+----------+\n",
+            )
+        );
+        for line in table.lines() {
+            assert_eq!(ui::display_width(line), 45, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn layout_fits_terminal_width() {
+        let table_data = table_data(&[("Faker", "Ahri", "Azir", "Caps")]);
+        assert_eq!(table_data.width(&COLUMNS), 45);
+        assert_eq!(table_data.stacked_width(&COLUMNS), 23);
+
+        let layout = |layout, width| table_data.layout(&COLUMNS, layout, width);
+        assert_eq!(layout(Layout::Auto, None), Layout::SideBySide);
+        assert_eq!(layout(Layout::Auto, Some(200)), Layout::SideBySide);
+        assert_eq!(layout(Layout::Auto, Some(45)), Layout::SideBySide);
+        assert_eq!(layout(Layout::Auto, Some(44)), Layout::Stacked);
+        assert_eq!(layout(Layout::Auto, Some(23)), Layout::Stacked);
+        assert_eq!(layout(Layout::Auto, Some(22)), Layout::Compact);
+        assert_eq!(layout(Layout::SideBySide, Some(10)), Layout::SideBySide);
+        assert_eq!(layout(Layout::Compact, None), Layout::Compact);
+    }
+
+    #[test]
+    fn stacked_tables_have_one_team_each() {
+        let table_data = table_data(&[("Faker", "Ahri", "Azir", "Caps"), ("Zeus", "", "Jax", "")]);
+
+        let [blue, red] = table_data.stacked_tables(&COLUMNS);
+        assert_eq!(
+            blue.to_string(),
+            concat!(
+                "+----------+----------+\n",
+                "| Summoner | Champion |\n",
+                "+----------+----------+\n",
+                "| Faker    | Ahri     |\n",
+                "| Zeus     |          |\n",
+                "+----------+----------+\n",
+            )
+        );
+        assert_eq!(
+            red.to_string(),
+            concat!(
+                "+----------+----------+\n",
+                "| Summoner | Champion |\n",
+                "+----------+----------+\n",
+                "| Caps     | Azir     |\n",
+                "|          | Jax      |\n",
+                "+----------+----------+\n",
+            )
+        );
+    }
+
+    #[test]
+    fn compact_lines_have_placeholders_for_empty_cells() {
+        let table_data = table_data(&[("Faker", "Ahri", "Azir", "Caps"), ("Zeus", "", "Jax", "")]);
+
+        let lines: Vec<String> = table_data
+            .compact_lines(&COLUMNS)
+            .iter()
+            .map(|line| strip_styles(line))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "Blue",
+                "  Faker | Ahri",
+                "  Zeus | -",
+                "Red",
+                "  Caps | Azir",
+                "  - | Jax",
+            ]
+        );
+    }
+
+    /// Removes the ANSI escape sequences of colors and styles
+    fn strip_styles(s: &str) -> String {
+        let mut result = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => {
+                    chars.by_ref().find(|&c| c == 'm');
+                }
+                c => result.push(c),
+            }
+        }
+        result
+    }
+}
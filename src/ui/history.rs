@@ -0,0 +1,80 @@
+use crate::pro_data::history::Snapshot;
+use crate::pro_data::Pro;
+use chrono::{Local, TimeZone};
+use prettytable::{format, row, Table};
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Prints the ladder history of each account of a pro as a table followed by an LP sparkline
+pub fn print(pro: &Pro, snapshots: &[Snapshot]) {
+    if snapshots.is_empty() {
+        println!("No ladder history for {pro}");
+        return;
+    }
+
+    let mut puuids: Vec<&str> = Vec::new();
+    for snapshot in snapshots {
+        if !puuids.contains(&snapshot.puuid.as_str()) {
+            puuids.push(&snapshot.puuid);
+        }
+    }
+
+    for puuid in puuids {
+        let account_snapshots: Vec<&Snapshot> =
+            snapshots.iter().filter(|s| s.puuid == puuid).collect();
+        let latest = account_snapshots.last().expect("account has snapshots");
+        println!("{pro} - {} [{}]", latest.riot_id, latest.platform);
+
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.set_titles(row!["Time", "Tier", "LP", "+/-", "W", "L", "GP"]);
+
+        let mut previous_lp = None;
+        for snapshot in &account_snapshots {
+            let time = match Local.timestamp_opt(snapshot.timestamp, 0).single() {
+                Some(t) => t.format("%Y-%m-%d %H:%M").to_string(),
+                None => snapshot.timestamp.to_string(),
+            };
+            let delta = match previous_lp {
+                Some(lp) => format!("{:+}", snapshot.league_points - lp),
+                None => "".to_string(),
+            };
+            previous_lp = Some(snapshot.league_points);
+
+            table.add_row(row![
+                time,
+                snapshot.tier,
+                r->snapshot.league_points,
+                r->delta,
+                r->snapshot.wins,
+                r->snapshot.losses,
+                r->snapshot.games_played()
+            ]);
+        }
+        crate::ui::print_table(&table);
+
+        let lps: Vec<i32> = account_snapshots.iter().map(|s| s.league_points).collect();
+        println!(
+            "LP {} {}-{}\n",
+            sparkline(&lps),
+            lps.iter().min().expect("lps is not empty"),
+            lps.iter().max().expect("lps is not empty")
+        );
+    }
+}
+
+fn sparkline(values: &[i32]) -> String {
+    let (min, max) = match (values.iter().min(), values.iter().max()) {
+        (Some(&min), Some(&max)) => (min, max),
+        _ => return String::new(),
+    };
+    let range = (max - min).max(1) as f32;
+
+    values
+        .iter()
+        .map(|&v| {
+            let level = ((v - min) as f32 / range * (SPARK_CHARS.len() - 1) as f32).round();
+            SPARK_CHARS[level as usize]
+        })
+        .collect()
+}
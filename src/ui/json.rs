@@ -0,0 +1,435 @@
+//! Machine-readable output for `--output json` and `--output ndjson`.
+//!
+//! Every document is a JSON object with a `schema_version` (currently 1, bumped on breaking
+//! changes) and a `type`. With `json` each command prints one document when it's done, with
+//! `ndjson` it prints one document per line as results come in.
+//!
+//! | Command       | `json` document        | `ndjson` documents                       |
+//! |---------------|------------------------|------------------------------------------|
+//! | live games    | `live`                 | `game` per game found, then `summary`    |
+//! | `leaderboard` | `leaderboard`          | `leaderboard_entry` per pro              |
+//! | `teams`       | `teams`                | `team` per team                          |
+//! | `history`     | `history`              | `snapshot` per snapshot                  |
+//! | `matches`     | `matches`              | `match_result` per game                  |
+//! | `validate`    | `validation`           | `problem` per problem                    |
+//! | `sync`        | `sync_report`          | `sync_report`                            |
+//! | `pro`         | `pro_change`           | `pro_change`                             |
+//! | `migrate`     | `migration`            | `migration`                              |
+//! | `watch`       | `game_started` and `game_ended` per event, as with `ndjson`       |
+//!
+//! The documents contain:
+//! - `live`: `games` (list of `game` without `schema_version` and `type`) and `summary`
+//! - `game`: `game_id`, `platform`, `start_time` (unix milliseconds, 0 if not started),
+//!   `average_lp` (`null` if no player is ranked) and `players`, each with `team` (`blue` or
+//!   `red`), `summoner_id`, `summoner_name`, `champion`, `pro` and `rank`, both `null` if unknown
+//! - `game_started`: like `game`, printed when a game is first seen, including games that were
+//!   already being played when `watch` started
+//! - `game_ended`: `game_id` and `platform`
+//! - `summary`: `games`, `pros_in_game` and `pros` counts
+//! - `leaderboard`: `entries`, each like a `leaderboard_entry`
+//! - `leaderboard_entry`: `position` (from 1, after filtering and sorting), `leaderboard_position`
+//!   (from 1, on the whole leaderboard sorted by LP), `pro`, `accounts` (the best one only,
+//!   unless `--all-accounts`) with `riot_id` and `rank`, and `change` (`null` without `--since`
+//!   or history) with `league_points`, `wins`, `losses`, `games_played`, and `position` and
+//!   `previous_position` (from 1, among the pros that have history, now and at `--since`)
+//! - `teams`: `teams`, each like a `team`
+//! - `team`: `position` (from 1), `team`, `team_name`, `pros` (count), `apex_players`,
+//!   `average_lp`, `total_lp`, `wins`, `losses`, `games_played`, `winrate` (percent, one decimal,
+//!   `null` without games) and `best_player` with `pro`, `riot_id` and `rank`
+//! - `history`, `matches`: `pro` and `snapshots` or `results`, each like a `snapshot` or
+//!   `match_result` with `pro` left out
+//! - `snapshot`: `timestamp` (unix seconds), `puuid`, `riot_id`, `platform`, `tier`,
+//!   `league_points`, `wins` and `losses`
+//! - `match_result`: `game_id`, `platform`, `game_end` (unix seconds), `puuid`, `riot_id`,
+//!   `champion`, `win`, `kills`, `deaths`, `assists`, `duration` (seconds) and `lp_change`
+//! - `validation`: `problems`, each like a `problem`: `location` and `message`
+//! - `sync_report`: `changes`, `failures` (each `riot_id` and `error`) and `missing`
+//! - `pro_change`: `action` (`add`, `remove` or `edit`), `pro` (after the change, or before it
+//!   for `remove`), `previous` (the `pro` before an `edit`, otherwise `null`) and `riot_ids` (of
+//!   the accounts added or removed)
+//! - `migration`: `pro_file`, and the number of `accounts`, `snapshots` and `results` imported
+//!
+//! A `pro` is an object with `name`, `team` and `team_name`. A `rank` is an object with `tier`,
+//! `league_points`, `wins`, `losses`, `winrate` (percent, one decimal, `null` without games) and
+//! `platform`.
+
+use riven::consts::Team;
+use serde::Serialize;
+
+use crate::pro_data::history::Snapshot;
+use crate::pro_data::leaderboard::{Entry, TeamStats};
+use crate::pro_data::sync::SyncReport;
+use crate::pro_data::tracker::MatchResult;
+use crate::pro_data::validate::Problem;
+use crate::pro_data::{Player, Pro, ProData, ProGame, RankedStats};
+use crate::ui::OutputFormat;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Document<'a, T: Serialize> {
+    schema_version: u32,
+    #[serde(rename = "type")]
+    document_type: &'a str,
+    #[serde(flatten)]
+    data: T,
+}
+
+fn print_document<T: Serialize>(document_type: &str, data: T) {
+    let document = Document {
+        schema_version: SCHEMA_VERSION,
+        document_type,
+        data,
+    };
+    println!(
+        "{}",
+        serde_json::to_string(&document).expect("output should serialize")
+    );
+}
+
+/// Prints a list as one document with the list under `field`, or as one document per item
+fn print_list<T: Serialize>(
+    format: OutputFormat,
+    document_type: &str,
+    item_type: &str,
+    field: &str,
+    items: Vec<T>,
+) {
+    match format {
+        OutputFormat::Ndjson => items
+            .into_iter()
+            .for_each(|item| print_document(item_type, item)),
+        _ => print_document(document_type, serde_json::json!({ field: items })),
+    }
+}
+
+#[derive(Serialize)]
+struct ProJson<'a> {
+    name: &'a str,
+    team: &'a str,
+    team_name: &'a str,
+}
+
+impl<'a> ProJson<'a> {
+    fn new(pro: &'a Pro) -> Self {
+        Self {
+            name: pro.name(),
+            team: pro.team(),
+            team_name: pro.team_name(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RankJson {
+    tier: String,
+    league_points: i32,
+    wins: i32,
+    losses: i32,
+    winrate: Option<f64>,
+    platform: String,
+}
+
+impl RankJson {
+    fn new(rank: &RankedStats) -> Self {
+        Self {
+            tier: rank.tier.to_string(),
+            league_points: rank.ranked_data.league_points,
+            wins: rank.ranked_data.wins,
+            losses: rank.ranked_data.losses,
+            /* Rounded like in tables, f32 digits beyond that are noise */
+            winrate: rank
+                .winrate()
+                .map(|winrate| (winrate as f64 * 10.0).round() / 10.0),
+            platform: rank.platform.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct GameJson {
+    game_id: i64,
+    platform: Option<String>,
+    start_time: i64,
+    average_lp: Option<i32>,
+    players: Vec<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct PlayerJson<'a> {
+    team: &'a str,
+    summoner_id: &'a str,
+    summoner_name: &'a str,
+    champion: &'a str,
+    pro: Option<ProJson<'a>>,
+    rank: Option<RankJson>,
+}
+
+impl GameJson {
+    pub fn new(game: &ProGame) -> Self {
+        let (first_team, second_team) = game.teams();
+        let player = |player: &Player| {
+            let participant = &player.current_game_participant;
+            let json = PlayerJson {
+                team: match participant.team_id {
+                    Team::BLUE => "blue",
+                    _ => "red",
+                },
+                summoner_id: &participant.summoner_id,
+                summoner_name: participant.summoner_name.trim_end(),
+                champion: participant.champion_id.name().unwrap_or("Unknown"),
+                pro: game.get_pro(&participant.summoner_id).map(ProJson::new),
+                rank: player.ranked_stats().as_ref().map(RankJson::new),
+            };
+            serde_json::to_value(json).expect("output should serialize")
+        };
+
+        let mut players: Vec<_> = first_team.into_iter().chain(second_team).collect();
+        players.sort_by_key(|p| p.current_game_participant.team_id != Team::BLUE);
+        let players = players.into_iter().map(player).collect();
+
+        Self {
+            game_id: game.game_id(),
+            platform: game.platform().map(|p| p.to_string()),
+            start_time: game.start_time(),
+            average_lp: game.average_lp(),
+            players,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SummaryJson {
+    pub games: usize,
+    pub pros_in_game: usize,
+    pub pros: usize,
+}
+
+/// Output of the live game lookup, printing games as they are found with NDJSON and all at once
+/// at the end otherwise
+pub struct LiveOutput {
+    format: OutputFormat,
+    games: Vec<GameJson>,
+}
+
+impl LiveOutput {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            games: Vec::new(),
+        }
+    }
+
+    pub fn game(&mut self, game: &ProGame) {
+        match self.format {
+            OutputFormat::Ndjson => print_document("game", GameJson::new(game)),
+            _ => self.games.push(GameJson::new(game)),
+        }
+    }
+
+    pub fn finish(self, summary: SummaryJson) {
+        match self.format {
+            OutputFormat::Ndjson => print_document("summary", summary),
+            _ => print_document(
+                "live",
+                serde_json::json!({ "games": self.games, "summary": summary }),
+            ),
+        }
+    }
+}
+
+pub fn print_game_started(game: &ProGame) {
+    print_document("game_started", GameJson::new(game));
+}
+
+pub fn print_game_ended(game: &ProGame) {
+    print_document(
+        "game_ended",
+        serde_json::json!({
+            "game_id": game.game_id(),
+            "platform": game.platform().map(|p| p.to_string()),
+        }),
+    );
+}
+
+#[derive(Serialize)]
+struct LeaderboardEntryJson<'a> {
+    position: usize,
+    leaderboard_position: usize,
+    pro: ProJson<'a>,
+    accounts: Vec<serde_json::Value>,
+    change: Option<serde_json::Value>,
+}
+
+/// Prints the pro leaderboard, see `ui::leaderboard::print`
+pub fn print_leaderboard(
+    format: OutputFormat,
+    pro_data: &ProData,
+    all_accounts: bool,
+    leaderboard: &[Entry],
+) {
+    let mut entries = Vec::new();
+
+    for (i, entry) in leaderboard.iter().enumerate() {
+        let pro = &entry.pro;
+        let accounts = match all_accounts {
+            true => pro_data.ranked_accounts(pro),
+            false => vec![entry.rank.clone()],
+        };
+        let change = entry.change.as_ref().map(|c| {
+            serde_json::json!({
+                "league_points": c.league_points,
+                "wins": c.wins,
+                "losses": c.losses,
+                "games_played": c.games_played(),
+                "position": c.position + 1,
+                "previous_position": c.previous_position + 1,
+            })
+        });
+
+        entries.push(LeaderboardEntryJson {
+            position: i + 1,
+            leaderboard_position: entry.position + 1,
+            pro: ProJson::new(pro),
+            accounts: accounts
+                .iter()
+                .map(|rank| {
+                    let riot_id = match pro.account(&rank.ranked_data.summoner_id) {
+                        Some(account) => account.riot_id().to_string(),
+                        None => rank.ranked_data.summoner_name.clone(),
+                    };
+                    serde_json::json!({ "riot_id": riot_id, "rank": RankJson::new(rank) })
+                })
+                .collect(),
+            change,
+        });
+    }
+
+    print_list(
+        format,
+        "leaderboard",
+        "leaderboard_entry",
+        "entries",
+        entries,
+    );
+}
+
+#[derive(Serialize)]
+struct TeamJson<'a> {
+    position: usize,
+    team: &'a str,
+    team_name: &'a str,
+    pros: usize,
+    apex_players: usize,
+    average_lp: i32,
+    total_lp: i32,
+    wins: i32,
+    losses: i32,
+    games_played: u32,
+    winrate: Option<f64>,
+    best_player: serde_json::Value,
+}
+
+/// Prints the team leaderboard, see `ui::teams::print`
+pub fn print_teams(format: OutputFormat, teams: &[TeamStats]) {
+    let teams = teams
+        .iter()
+        .enumerate()
+        .map(|(i, team)| {
+            let (pro, rank) = team.best_player();
+            let riot_id = match pro.account(&rank.ranked_data.summoner_id) {
+                Some(account) => account.riot_id().to_string(),
+                None => rank.ranked_data.summoner_name.clone(),
+            };
+            TeamJson {
+                position: i + 1,
+                team: &team.short_name,
+                team_name: &team.full_name,
+                pros: team.pros,
+                apex_players: team.apex_players(),
+                average_lp: team.average_lp(),
+                total_lp: team.total_lp(),
+                wins: team.wins(),
+                losses: team.losses(),
+                games_played: team.games_played(),
+                winrate: team
+                    .winrate()
+                    .map(|winrate| (winrate as f64 * 10.0).round() / 10.0),
+                best_player: serde_json::json!({
+                    "pro": ProJson::new(pro),
+                    "riot_id": riot_id,
+                    "rank": RankJson::new(rank),
+                }),
+            }
+        })
+        .collect();
+
+    print_list(format, "teams", "team", "teams", teams);
+}
+
+/// Prints the ladder history of a pro
+pub fn print_history(format: OutputFormat, pro: &Pro, snapshots: &[Snapshot]) {
+    match format {
+        OutputFormat::Ndjson => snapshots.iter().for_each(|s| print_document("snapshot", s)),
+        _ => print_document(
+            "history",
+            serde_json::json!({ "pro": ProJson::new(pro), "snapshots": snapshots }),
+        ),
+    }
+}
+
+/// Prints the recorded match results of a pro
+pub fn print_matches(format: OutputFormat, pro: &Pro, results: &[MatchResult]) {
+    match format {
+        OutputFormat::Ndjson => results
+            .iter()
+            .for_each(|r| print_document("match_result", r)),
+        _ => print_document(
+            "matches",
+            serde_json::json!({ "pro": ProJson::new(pro), "results": results }),
+        ),
+    }
+}
+
+pub fn print_problems(format: OutputFormat, problems: &[Problem]) {
+    print_list(
+        format,
+        "validation",
+        "problem",
+        "problems",
+        problems.iter().collect(),
+    );
+}
+
+pub fn print_sync_report(report: &SyncReport) {
+    print_document("sync_report", report);
+}
+
+/// Prints what `pro add`, `pro remove` or `pro edit` changed
+/// # Parameters
+/// `pro` - the pro after the change, or before it for `remove`
+/// `previous` - the pro before an `edit`
+/// `riot_ids` - Riot IDs of the accounts added or removed
+pub fn print_pro_change(action: &str, pro: &Pro, previous: Option<&Pro>, riot_ids: &[String]) {
+    print_document(
+        "pro_change",
+        serde_json::json!({
+            "action": action,
+            "pro": ProJson::new(pro),
+            "previous": previous.map(ProJson::new),
+            "riot_ids": riot_ids,
+        }),
+    );
+}
+
+pub fn print_migration(pro_file: &str, accounts: usize, snapshots: usize, results: usize) {
+    print_document(
+        "migration",
+        serde_json::json!({
+            "pro_file": pro_file,
+            "accounts": accounts,
+            "snapshots": snapshots,
+            "results": results,
+        }),
+    );
+}
@@ -1,22 +1,83 @@
+use crate::pro_data::history::LadderChange;
+use crate::pro_data::leaderboard::Entry;
 use crate::pro_data::ProData;
-use prettytable::{color, format, row, Attr, Table};
+use prettytable::{format, Attr, Cell, Row, Table};
 
-pub fn print(pro_data: &ProData) {
+/// Prints the pro leaderboard
+/// # Parameters
+/// `all_accounts` - whether to print every ranked account of each pro instead of the best one
+/// `entries` - the pros to print, see `pro_data::leaderboard::entries`
+/// `changes` - whether to print the ladder changes of the entries as extra columns
+pub fn print(pro_data: &ProData, all_accounts: bool, entries: &[Entry], changes: bool) {
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_CLEAN);
-    let leaderboard = pro_data.pro_leaderboard();
-    for (i, (pro, rank)) in leaderboard.iter().enumerate() {
-        let mut row = row![format!("{}.", i + 1), pro, rank];
-        let color = match rank.tier {
-            riven::consts::Tier::CHALLENGER => color::BRIGHT_YELLOW,
-            riven::consts::Tier::GRANDMASTER => color::RED,
-            riven::consts::Tier::MASTER => color::MAGENTA,
-            _ => panic!("Rank should never be below master"),
+    for (i, entry) in entries.iter().enumerate() {
+        let pro = &entry.pro;
+        let accounts = match all_accounts {
+            true => pro_data.ranked_accounts(pro),
+            false => vec![entry.rank.clone()],
         };
-        for cell in row.iter_mut() {
-            cell.style(Attr::ForegroundColor(color))
+        let change = entry.change.as_ref();
+
+        for (j, account_rank) in accounts.iter().enumerate() {
+            let riot_id = match pro.account(&account_rank.ranked_data.summoner_id) {
+                Some(account) => account.riot_id().to_string(),
+                None => account_rank.ranked_data.summoner_name.clone(),
+            };
+
+            let mut cells = Vec::new();
+            match j {
+                0 => cells.push(format!("{}.", i + 1)),
+                _ => cells.push("".to_string()),
+            }
+            if changes {
+                cells.push(match j {
+                    0 => movement_string(change),
+                    _ => "".to_string(),
+                });
+            }
+            match j {
+                0 => cells.push(pro.to_string()),
+                _ => cells.push("".to_string()),
+            }
+            cells.push(riot_id);
+            cells.push(account_rank.platform.to_string());
+            cells.push(account_rank.to_string());
+            if changes {
+                match (j, change) {
+                    (0, Some(c)) => {
+                        cells.push(format!("{:+}LP", c.league_points));
+                        cells.push(format!("{}gp", c.games_played()));
+                        cells.push(format!("{}W {}L", c.wins, c.losses));
+                    }
+                    _ => cells.extend(["".to_string(), "".to_string(), "".to_string()]),
+                }
+            }
+
+            let color = crate::ui::theme()
+                .tier(account_rank.tier)
+                .expect("Rank should never be below master");
+            let mut row = Row::new(cells.iter().map(|c| Cell::new(c)).collect());
+            for cell in row.iter_mut() {
+                cell.style(Attr::ForegroundColor(color.term()))
+            }
+            table.add_row(row);
+        }
+    }
+    crate::ui::print_table(&table);
+}
+
+/// Arrow showing how many positions a pro climbed or dropped on the leaderboard, among the pros
+/// that have a reference snapshot
+fn movement_string(change: Option<&LadderChange>) -> String {
+    match change {
+        Some(c) if c.previous_position > c.position => {
+            format!("▲{}", c.previous_position - c.position)
+        }
+        Some(c) if c.previous_position < c.position => {
+            format!("▼{}", c.position - c.previous_position)
         }
-        table.add_row(row);
+        Some(_) => "=".to_string(),
+        None => "new".to_string(),
     }
-    table.printstd();
 }
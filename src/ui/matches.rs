@@ -0,0 +1,65 @@
+use crate::pro_data::tracker::MatchResult;
+use crate::pro_data::Pro;
+use chrono::{Local, TimeZone};
+use prettytable::{color, format, row, Attr, Table};
+
+/// Prints the recorded match results of a pro, newest first
+pub fn print(pro: &Pro, results: &[MatchResult]) {
+    if results.is_empty() {
+        println!("No recorded games for {pro}");
+        return;
+    }
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_CLEAN);
+    table.set_titles(row![
+        "Ended", "Account", "Champion", "Result", "KDA", "Length", "LP"
+    ]);
+
+    for result in results.iter().rev() {
+        let ended = match Local.timestamp_opt(result.game_end, 0).single() {
+            Some(t) => t.format("%Y-%m-%d %H:%M").to_string(),
+            None => result.game_end.to_string(),
+        };
+        let lp_change = match result.lp_change {
+            Some(lp) => format!("{lp:+}"),
+            None => "?".to_string(),
+        };
+
+        let mut row = row![
+            ended,
+            result.riot_id,
+            result.champion,
+            if result.win { "Win" } else { "Loss" },
+            format!("{}/{}/{}", result.kills, result.deaths, result.assists),
+            format!("{:02}:{:02}", result.duration / 60, result.duration % 60),
+            r->lp_change
+        ];
+        let color = if result.win { color::GREEN } else { color::RED };
+        row.get_mut_cell(3)
+            .expect("row has a result cell")
+            .style(Attr::ForegroundColor(color));
+        table.add_row(row);
+    }
+    crate::ui::print_table(&table);
+}
+
+/// Describes a result in one line, e.g. "won as Ahri 5/2/7 in 31:04 (+18LP)"
+pub fn result_string(result: &MatchResult) -> String {
+    let lp_change = match result.lp_change {
+        Some(lp) => format!(" ({lp:+}LP)"),
+        None => "".to_string(),
+    };
+
+    format!(
+        "{} as {} {}/{}/{} in {:02}:{:02}{}",
+        if result.win { "won" } else { "lost" },
+        result.champion,
+        result.kills,
+        result.deaths,
+        result.assists,
+        result.duration / 60,
+        result.duration % 60,
+        lp_change
+    )
+}
@@ -0,0 +1,52 @@
+use crate::pro_data::leaderboard::TeamStats;
+use prettytable::{format, row, Attr, Table};
+
+/// Prints the team leaderboard
+pub fn print(teams: &[TeamStats]) {
+    if teams.is_empty() {
+        println!("No team has a ranked pro");
+        return;
+    }
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_CLEAN);
+    table.set_titles(row![
+        "",
+        "Team",
+        "Apex",
+        "Avg LP",
+        "Total LP",
+        "Games",
+        "Winrate",
+        "Best player"
+    ]);
+
+    for (i, team) in teams.iter().enumerate() {
+        let (best_pro, best_rank) = team.best_player();
+        let mut row = row![
+            format!("{}.", i + 1),
+            format!("{} {}", team.short_name, team.full_name),
+            r->format!("{}/{}", team.apex_players(), team.pros),
+            r->format!("{}LP", team.average_lp()),
+            r->format!("{}LP", team.total_lp()),
+            r->team.games_played(),
+            r->team
+                .winrate()
+                .map_or("-".to_string(), |winrate| format!("{winrate:.1}%")),
+            format!(
+                "{} {} {}LP",
+                best_pro.name(),
+                best_rank.tier_abbreviation(),
+                best_rank.ranked_data.league_points
+            )
+        ];
+        let color = crate::ui::theme()
+            .tier(best_rank.tier)
+            .expect("Rank should never be below master");
+        row.get_mut_cell(7)
+            .expect("row has a best player cell")
+            .style(Attr::ForegroundColor(color.term()));
+        table.add_row(row);
+    }
+    crate::ui::print_table(&table);
+}
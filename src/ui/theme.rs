@@ -0,0 +1,90 @@
+use prettytable::color;
+use riven::consts::{Team, Tier};
+
+/// One of the 16 standard terminal colors, named like `blue` or `bright-blue` in the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeColor {
+    Black = 0,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl ThemeColor {
+    /// The color for prettytable cells
+    pub fn term(self) -> color::Color {
+        self as color::Color
+    }
+
+    /// The color for text printed with yansi
+    pub fn yansi(self) -> yansi::Color {
+        yansi::Color::Fixed(self as u8)
+    }
+
+    /// The color for the TUI
+    pub fn ratatui(self) -> ratatui::style::Color {
+        ratatui::style::Color::Indexed(self as u8)
+    }
+}
+
+/// Colors used by every renderer, set in the `[theme]` section of the config file
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub blue: ThemeColor,
+    pub red: ThemeColor,
+    /// Color of pro names in games, the color of their team if not set
+    pub pro: Option<ThemeColor>,
+    pub challenger: ThemeColor,
+    pub grandmaster: ThemeColor,
+    pub master: ThemeColor,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            blue: ThemeColor::Blue,
+            red: ThemeColor::Red,
+            pro: None,
+            challenger: ThemeColor::BrightYellow,
+            grandmaster: ThemeColor::Red,
+            master: ThemeColor::Magenta,
+        }
+    }
+}
+
+impl Theme {
+    pub fn team(&self, team: Team) -> ThemeColor {
+        match team {
+            Team::BLUE => self.blue,
+            _ => self.red,
+        }
+    }
+
+    /// Color of a pro's name on the given team
+    pub fn pro(&self, team: Team) -> ThemeColor {
+        self.pro.unwrap_or_else(|| self.team(team))
+    }
+
+    /// Color of an apex tier, `None` for tiers below master
+    pub fn tier(&self, tier: Tier) -> Option<ThemeColor> {
+        match tier {
+            Tier::CHALLENGER => Some(self.challenger),
+            Tier::GRANDMASTER => Some(self.grandmaster),
+            Tier::MASTER => Some(self.master),
+            _ => None,
+        }
+    }
+}
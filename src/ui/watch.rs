@@ -0,0 +1,66 @@
+use std::io::Write;
+
+use chrono::{DateTime, Local};
+
+use crate::pro_data::{ProData, ProGame};
+use crate::ui::game::{self, Column, Layout};
+
+/// Moves the cursor to the top left corner and clears the terminal
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+
+/// Redraws the watch screen: a status line, every live game and the latest events
+/// # Parameters
+/// `clear` - whether to clear the terminal first, only wanted when it is one
+/// `updated_at` - when the games were last looked up
+/// `next_update` - seconds until the games are looked up again
+/// `events` - lines describing what happened, oldest first
+pub async fn print(
+    pro_data: &ProData,
+    columns: &[Column],
+    layout: Layout,
+    clear: bool,
+    updated_at: DateTime<Local>,
+    next_update: u64,
+    events: &[String],
+) {
+    if clear {
+        print!("{CLEAR_SCREEN}");
+        std::io::stdout()
+            .flush()
+            .expect("stdout should be writable");
+    }
+
+    println!(
+        "Watching {} pro(s), {} in {} live game(s). Updated {}, next update in {}s",
+        pro_data.pros_count(),
+        pro_data.pros_in_game_count(),
+        pro_data.games_count(),
+        updated_at.format("%X"),
+        next_update,
+    );
+    for pro_game in pro_data.games() {
+        game::print(&pro_game, columns, layout)
+            .await
+            .expect("printing should succeed");
+    }
+
+    if !events.is_empty() {
+        println!();
+        for event in events {
+            println!("{event}");
+        }
+    }
+}
+
+/// Describes a game for the event log, e.g. `game 123 on EUW1 with G2 Caps, FNC Humanoid`
+pub fn game_string(pro_game: &ProGame) -> String {
+    let pros: Vec<String> = pro_game.pros().iter().map(|p| p.to_string()).collect();
+    match pro_game.platform() {
+        Some(platform) => format!(
+            "game {} on {platform} with {}",
+            pro_game.game_id(),
+            pros.join(", ")
+        ),
+        None => format!("game {} with {}", pro_game.game_id(), pros.join(", ")),
+    }
+}
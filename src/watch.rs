@@ -0,0 +1,215 @@
+//! Watch mode: looks up live games on an interval, keeping the pro data and the games found so
+//! far between lookups so that only pros who aren't in a known game are looked up again.
+
+use std::collections::{HashSet, VecDeque};
+use std::io::IsTerminal;
+use std::process;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use futures::stream::{self, StreamExt};
+use riven::reqwest::StatusCode;
+
+use crate::pro_data::tracker::MatchResult;
+use crate::pro_data::{self, ProData, ProGame};
+use crate::ui::{self, OutputFormat};
+use crate::Config;
+
+/// Number of events shown below the games
+const EVENT_LOG_LENGTH: usize = 10;
+
+/// How often the screen is redrawn to keep the game clocks ticking
+const REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Keeps the pro data and the games found so far between lookups
+pub struct Watcher {
+    pro_data: ProData,
+    first_round: bool,
+}
+
+/// What changed in one lookup of every pro
+pub struct Round {
+    /// Games seen for the first time, including the ones already being played in the first round
+    pub started: Vec<Arc<ProGame>>,
+    pub ended: Vec<Arc<ProGame>>,
+    /// Results of games that were recorded in this round
+    pub results: Vec<MatchResult>,
+    /// Errors that didn't stop the round
+    pub errors: Vec<String>,
+    pub first_round: bool,
+}
+
+impl Watcher {
+    pub fn new(pro_data: ProData) -> Self {
+        Self {
+            pro_data,
+            first_round: true,
+        }
+    }
+
+    pub fn pro_data(&self) -> &ProData {
+        &self.pro_data
+    }
+
+    /// Forgets the games that ended, refreshes the ladders when they are older than the ladder
+    /// TTL, looks up every pro not in a game and records the results of finished games
+    /// # Returns
+    /// An error if looking up games can't work at all, e.g. because of a bad API key
+    pub async fn round(&mut self, c: &Config) -> Result<Round, String> {
+        let mut errors = Vec::new();
+        let ended = self.pro_data.remove_finished_games().await;
+        let ladder_refreshed = self
+            .pro_data
+            .refresh_top_leagues(c)
+            .await
+            .unwrap_or_else(|e| {
+                errors.push(format!("Error when refreshing top leagues: {e}"));
+                false
+            });
+
+        let known: HashSet<i64> = self.pro_data.games().iter().map(|g| g.game_id()).collect();
+        errors.extend(find_games(c, &self.pro_data).await?);
+        let started = self
+            .pro_data
+            .games()
+            .into_iter()
+            .filter(|g| !known.contains(&g.game_id()))
+            .collect();
+
+        if let Err(e) = pro_data::tracker::track_games(c, &self.pro_data) {
+            errors.push(format!("Error when tracking games: {e}"));
+        }
+        /* Results can only be recorded once a game has ended and the ladder reflects it */
+        let mut results = Vec::new();
+        if !ended.is_empty() || ladder_refreshed || self.first_round {
+            match pro_data::tracker::resolve_finished(c, &self.pro_data).await {
+                Ok(r) => results = r,
+                Err(e) => errors.push(format!("Error when recording game results: {e}")),
+            }
+        }
+
+        let first_round = self.first_round;
+        self.first_round = false;
+        Ok(Round {
+            started,
+            ended,
+            results,
+            errors,
+            first_round,
+        })
+    }
+}
+
+/// Describes a recorded result for the event log
+pub fn result_event(result: &MatchResult) -> String {
+    format!(
+        "Recorded result of game {}: {} {}",
+        result.game_id,
+        result.riot_id,
+        ui::matches::result_string(result)
+    )
+}
+
+/// Watches live games until the process is killed
+pub async fn watch(c: &Config, pro_data: ProData) {
+    /* Redrawing in place only makes sense in a terminal, otherwise the games are printed once per
+     * lookup */
+    let is_terminal = std::io::stdout().is_terminal();
+    let mut watcher = Watcher::new(pro_data);
+    let mut events = VecDeque::new();
+
+    loop {
+        let next_round = Instant::now() + c.watch_interval;
+        let round = watcher.round(c).await.unwrap_or_else(|e| {
+            eprintln!("ERROR: {e}");
+            process::exit(1);
+        });
+        let mut log = |event: String| match c.output {
+            OutputFormat::Table => {
+                events.push_back(format!("{} {event}", Local::now().format("%X")))
+            }
+            _ => eprintln!("{event}"),
+        };
+
+        for error in round.errors {
+            log(error);
+        }
+        for game in &round.ended {
+            match c.output {
+                OutputFormat::Table => log(format!("Ended: {}", ui::watch::game_string(game))),
+                _ => ui::json::print_game_ended(game),
+            }
+        }
+        for game in &round.started {
+            match c.output {
+                /* Games that were already being played when watching started aren't news */
+                OutputFormat::Table if round.first_round => (),
+                OutputFormat::Table => log(format!("Started: {}", ui::watch::game_string(game))),
+                _ => ui::json::print_game_started(game),
+            }
+        }
+        for result in &round.results {
+            log(result_event(result));
+        }
+
+        while events.len() > EVENT_LOG_LENGTH {
+            events.pop_front();
+        }
+
+        if c.output != OutputFormat::Table {
+            tokio::time::sleep_until(next_round.into()).await;
+            continue;
+        }
+
+        let updated_at = Local::now();
+        let events: Vec<String> = events.iter().cloned().collect();
+        loop {
+            let next_update = next_round.saturating_duration_since(Instant::now());
+            ui::watch::print(
+                watcher.pro_data(),
+                &c.columns,
+                c.layout,
+                is_terminal,
+                updated_at,
+                next_update.as_secs(),
+                &events,
+            )
+            .await;
+
+            if !is_terminal || next_update <= REDRAW_INTERVAL {
+                tokio::time::sleep(next_update).await;
+                break;
+            }
+            tokio::time::sleep(REDRAW_INTERVAL).await;
+        }
+    }
+}
+
+/// Looks up every pro that isn't in a game found already
+/// # Returns
+/// The errors of lookups that failed, or an error if the API key was rejected
+async fn find_games(c: &Config, pro_data: &ProData) -> Result<Vec<String>, String> {
+    let mut errors = Vec::new();
+    let mut lookups = stream::iter(pro_data.get_pros())
+        .map(|pro| async move {
+            /* Skip pros that were found in a game by an earlier lookup */
+            if pro_data.is_in_game(&pro) {
+                return (pro, Ok(None));
+            }
+            let result = pro_data.fetch_game(&pro).await;
+            (pro, result)
+        })
+        .buffer_unordered(c.jobs.get());
+
+    while let Some((pro, result)) = lookups.next().await {
+        if let Err(e) = result {
+            if e.status_code() == Some(StatusCode::FORBIDDEN) {
+                return Err("403 received, probably due to bad API key".to_string());
+            }
+            errors.push(format!("Error when fetching game for {pro}: {e}"));
+        }
+    }
+
+    Ok(errors)
+}
@@ -0,0 +1,333 @@
+//! Runs the CLI end to end against `mock-riot` serving `fixtures/mock`
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+/// The mock server, killed when dropped
+struct MockServer {
+    child: Child,
+    api_url: String,
+}
+
+impl MockServer {
+    fn start() -> Self {
+        /* Let the OS pick a free port, then hand it to the server */
+        let address = TcpListener::bind("127.0.0.1:0")
+            .and_then(|l| l.local_addr())
+            .expect("should find a free port");
+        let child = Command::new(env!("CARGO_BIN_EXE_mock-riot"))
+            .arg(fixtures().join("mock"))
+            .arg(address.to_string())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("mock-riot should start");
+
+        let started = Instant::now();
+        while TcpStream::connect(address).is_err() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "mock-riot did not start listening"
+            );
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        Self {
+            child,
+            api_url: format!("http://{address}/{{}}"),
+        }
+    }
+
+    /// Runs lol-pros against the server, see `lol_pros`
+    fn run(&self, dir: &Path, pro_file: &Path, args: &[&str]) -> Output {
+        lol_pros(&self.api_url, dir, pro_file, args)
+            .output()
+            .expect("lol-pros should run")
+    }
+}
+
+/// A command running lol-pros against `api_url` with its own config, cache and data directories
+fn lol_pros(api_url: &str, dir: &Path, pro_file: &Path, args: &[&str]) -> Command {
+    fs::write(dir.join("config.toml"), "").expect("should write config");
+    let mut command = Command::new(env!("CARGO_BIN_EXE_lol-pros"));
+    command
+        .env("RGAPI_KEY", "mock")
+        .env_remove("NO_COLOR")
+        .env_remove("CLICOLOR_FORCE")
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .arg("--api-url")
+        .arg(api_url)
+        .arg("--cache-dir")
+        .arg(dir.join("cache"))
+        .arg("--data-dir")
+        .arg(dir.join("data"))
+        .arg("-p")
+        .arg(pro_file)
+        .args(["--color", "never"])
+        .args(args);
+    command
+}
+
+/// Forwards requests to the mock server, except that requests to a route never get an answer
+/// # Returns
+/// The API URL of the proxy
+fn stalling_proxy(server: &MockServer, stalled_route: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("should bind proxy");
+    let address = listener.local_addr().expect("proxy should have an address");
+    let upstream: SocketAddr = server
+        .api_url
+        .trim_start_matches("http://")
+        .trim_end_matches("/{}")
+        .parse()
+        .expect("API URL should have an address");
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            thread::spawn(move || {
+                let mut request = Vec::new();
+                let mut buffer = [0; 4096];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+                if String::from_utf8_lossy(&request).contains(&format!("/{stalled_route}/")) {
+                    /* Keep the connection open without answering */
+                    thread::sleep(Duration::from_secs(120));
+                    return;
+                }
+                let mut upstream = TcpStream::connect(upstream).expect("should reach mock-riot");
+                upstream
+                    .write_all(&request)
+                    .expect("should forward request");
+                let mut response = Vec::new();
+                upstream
+                    .read_to_end(&mut response)
+                    .expect("should read response");
+                let _ = stream.write_all(&response);
+            });
+        }
+    });
+
+    format!("http://{address}/{{}}")
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+/// An empty directory for one test
+fn test_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("should create test directory");
+    dir
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).expect("stdout should be UTF-8")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).expect("stderr should be UTF-8")
+}
+
+#[test]
+fn live_games() {
+    let server = MockServer::start();
+    let dir = test_dir("live_games");
+    let output = server.run(&dir, &fixtures().join("mock/pros.csv"), &["-o", "json"]);
+    assert!(output.status.success(), "{output:?}");
+
+    let live: Value = serde_json::from_str(&stdout(&output)).expect("output should be JSON");
+    assert_eq!(live["type"], "live");
+    let games = live["games"].as_array().expect("games should be a list");
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0]["game_id"], 6500000001_i64);
+    assert_eq!(games[0]["platform"], "EUW1");
+
+    let pros: Vec<(&str, &str)> = games[0]["players"]
+        .as_array()
+        .expect("players should be a list")
+        .iter()
+        .filter(|p| !p["pro"].is_null())
+        .map(|p| {
+            let name = p["pro"]["name"].as_str().expect("pro should have a name");
+            (name, p["team"].as_str().expect("player should have a team"))
+        })
+        .collect();
+    assert_eq!(pros, [("Nova", "blue"), ("Ember", "red")]);
+    assert_eq!(live["summary"]["games"], 1);
+    assert_eq!(live["summary"]["pros_in_game"], 2);
+    assert_eq!(live["summary"]["pros"], 4);
+}
+
+#[test]
+fn leaderboard() {
+    let server = MockServer::start();
+    let dir = test_dir("leaderboard");
+    let output = server.run(&dir, &fixtures().join("mock/pros.csv"), &["leaderboard"]);
+    assert!(output.status.success(), "{output:?}");
+
+    assert_eq!(
+        stdout(&output),
+        concat!(
+            " 1.  KGX Ember  Ember#KGX  EUW1  C1 1530LP 540gp 55.6% \n",
+            " 2.  AUR Nova   Nova#AUR   EUW1  C1 1204LP 321gp 56.1% \n",
+            " 3.  AUR Tidal  Tidal#AUR  EUW1  GM 612LP 400gp 52.5% \n",
+            " 4.  KGX Quill  Quill#KGX  EUW1  M 88LP 185gp 51.4% \n",
+        )
+    );
+}
+
+#[test]
+fn sync() {
+    let server = MockServer::start();
+    let dir = test_dir("sync");
+    /* Nova's IDs are looked up again from the Riot ID */
+    let pro_file = dir.join("pros.csv");
+    let pros = fs::read_to_string(fixtures().join("mock/pros.csv")).expect("should read pros");
+    fs::write(&pro_file, pros.replace(",puuid-nova,sid-nova", ",,")).expect("should write pros");
+
+    let output = server.run(&dir, &pro_file, &["-o", "json", "sync"]);
+    /* EmberKR#KGX has no fixture, so the sync reports a failure */
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+
+    let report: Value = serde_json::from_str(&stdout(&output)).expect("output should be JSON");
+    assert_eq!(report["type"], "sync_report");
+    assert_eq!(
+        report["changes"],
+        serde_json::json!(["AUR Nova Nova#AUR: PUUID puuid-nova summoner ID sid-nova"])
+    );
+    assert_eq!(report["failures"][0]["riot_id"], "EmberKR#KGX");
+    assert_eq!(report["missing"], serde_json::json!([]));
+
+    let synced = fs::read_to_string(&pro_file).expect("should read synced pros");
+    assert_eq!(synced, pros);
+}
+
+#[test]
+fn ladder_cache() {
+    let server = MockServer::start();
+    let dir = test_dir("ladder_cache");
+    let pro_file = fixtures().join("mock/pros.csv");
+    let run = |args: &[&str]| {
+        let output = server.run(&dir, &pro_file, args);
+        assert!(output.status.success(), "{output:?}");
+        stderr(&output)
+    };
+
+    assert!(run(&["leaderboard"]).contains("Getting top leagues for EUW1..."));
+    /* A fresh cache is used */
+    let cached = run(&["leaderboard"]);
+    assert!(
+        cached.contains("Using top leagues for EUW1 cached"),
+        "{cached}"
+    );
+    assert!(!cached.contains("Getting top leagues"), "{cached}");
+    assert!(!dir
+        .join("cache/ladders")
+        .read_dir()
+        .expect("cache dir")
+        .any(|f| {
+            let name = f.expect("cache file").file_name();
+            name.to_string_lossy().ends_with(".tmp")
+        }));
+
+    /* --refresh and a stale cache fetch again */
+    assert!(run(&["--refresh", "leaderboard"]).contains("Getting top leagues for EUW1..."));
+    assert!(run(&["--ladder-ttl", "0", "leaderboard"]).contains("Getting top leagues for EUW1..."));
+}
+
+/// Starts a sync that stalls on the KR account, and kills it once both EUW accounts are resolved
+fn interrupt_sync(server: &MockServer, dir: &Path, pro_file: &Path) {
+    let api_url = stalling_proxy(server, "ASIA");
+    let mut child = lol_pros(&api_url, dir, pro_file, &["sync"])
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("lol-pros should start");
+    let stderr = child.stderr.take().expect("stderr should be piped");
+    for line in BufReader::new(stderr).lines() {
+        let line = line.expect("stderr should be UTF-8");
+        if line.starts_with("Resolved Tidal#AUR") {
+            break;
+        }
+    }
+    child.kill().expect("sync should still be running");
+    child.wait().expect("sync should stop");
+}
+
+#[test]
+fn sync_resumes() {
+    let server = MockServer::start();
+    let dir = test_dir("sync_resumes");
+    let pro_file = dir.join("pros.csv");
+    let pros = fs::read_to_string(fixtures().join("mock/pros.csv")).expect("should read pros");
+    let unsynced = pros
+        .replace(",puuid-nova,sid-nova", ",,")
+        .replace(",puuid-tidal,sid-tidal", ",,");
+    fs::write(&pro_file, &unsynced).expect("should write pros");
+
+    interrupt_sync(&server, &dir, &pro_file);
+    assert_eq!(fs::read_to_string(&pro_file).expect("pros"), unsynced);
+    assert!(dir.join("cache/sync_progress.json").exists());
+
+    /* Both accounts come from the progress, only the KR account is looked up and fails */
+    let output = server.run(&dir, &pro_file, &["sync"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("Resuming interrupted sync with 2 account(s) already resolved"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("Resolved"), "{stderr}");
+    assert_eq!(fs::read_to_string(&pro_file).expect("pros"), pros);
+    assert!(!dir.join("cache/sync_progress.json").exists());
+}
+
+#[test]
+fn sync_discards_progress_of_edited_file() {
+    let server = MockServer::start();
+    let dir = test_dir("sync_discards_progress_of_edited_file");
+    let pro_file = dir.join("pros.csv");
+    let pros = fs::read_to_string(fixtures().join("mock/pros.csv")).expect("should read pros");
+    let unsynced = pros
+        .replace(",puuid-nova,sid-nova", ",,")
+        .replace(",puuid-tidal,sid-tidal", ",,");
+    fs::write(&pro_file, &unsynced).expect("should write pros");
+
+    interrupt_sync(&server, &dir, &pro_file);
+    /* Nova is removed, so the progress is stale and Tidal is resolved again */
+    let edited = unsynced.replace("Nova,AUR,Aurora Esports,Nova#AUR,EUW1,,\n", "");
+    assert_ne!(edited, unsynced);
+    fs::write(&pro_file, &edited).expect("should write pros");
+
+    let output = server.run(&dir, &pro_file, &["sync"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = stderr(&output);
+    assert!(!stderr.contains("Resuming"), "{stderr}");
+    assert!(stderr.contains("Resolved Tidal#AUR of Tidal"), "{stderr}");
+    assert_eq!(
+        fs::read_to_string(&pro_file).expect("pros"),
+        pros.replace(
+            "Nova,AUR,Aurora Esports,Nova#AUR,EUW1,puuid-nova,sid-nova\n",
+            ""
+        )
+    );
+}